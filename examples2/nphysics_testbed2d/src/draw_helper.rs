@@ -19,7 +19,7 @@ pub fn draw_colls(window:  &mut graphics::RenderWindow,
 
     for c in collisions.iter() {
         match *c {
-            Constraint::RBRB(_, _, c) => {
+            Constraint::RBRB(_, _, c, _) => {
                 draw_line(
                     window,
                     &c.world1,
@@ -54,6 +54,46 @@ pub fn draw_colls(window:  &mut graphics::RenderWindow,
                     &na::translation(&bis.borrow().anchor2_pos()).translate(&na::orig()),
                     &Color::new_RGB(255, 0, 0)
                 );
+            },
+            Constraint::Revolute(ref r) => {
+                draw_line(
+                    window,
+                    &na::translation(&r.borrow().anchor1_pos()).translate(&na::orig()),
+                    &na::translation(&r.borrow().anchor2_pos()).translate(&na::orig()),
+                    &Color::new_RGB(255, 0, 0)
+                );
+            },
+            Constraint::Prismatic(ref p) => {
+                draw_line(
+                    window,
+                    &na::translation(&p.borrow().anchor1_pos()).translate(&na::orig()),
+                    &na::translation(&p.borrow().anchor2_pos()).translate(&na::orig()),
+                    &Color::new_RGB(255, 0, 0)
+                );
+            },
+            Constraint::Spring(ref s) => {
+                draw_line(
+                    window,
+                    &s.borrow().anchor1_pos(),
+                    &s.borrow().anchor2_pos(),
+                    &Color::new_RGB(255, 0, 0)
+                );
+            },
+            Constraint::Rope(ref r) => {
+                draw_line(
+                    window,
+                    &r.borrow().anchor1_pos(),
+                    &r.borrow().anchor2_pos(),
+                    &Color::new_RGB(255, 0, 0)
+                );
+            },
+            Constraint::Custom(ref c) => {
+                draw_line(
+                    window,
+                    &na::translation(&c.borrow().anchor1_pos()).translate(&na::orig()),
+                    &na::translation(&c.borrow().anchor2_pos()).translate(&na::orig()),
+                    &Color::new_RGB(255, 0, 0)
+                );
             }
         }
     }
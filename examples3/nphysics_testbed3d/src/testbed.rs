@@ -421,7 +421,7 @@ fn draw_collisions(window: &mut Window, physics: &mut World) {
 
     for c in collisions.iter() {
         match *c {
-            Constraint::RBRB(_, _, ref c) => {
+            Constraint::RBRB(_, _, ref c, _) => {
                 window.draw_line(&c.world1, &c.world2, &Pnt3::new(1.0, 0.0, 0.0));
 
                 let center = na::center(&c.world1, &c.world2);
@@ -435,6 +435,23 @@ fn draw_collisions(window: &mut Window, physics: &mut World) {
             Constraint::Fixed(ref f) => {
                 // FIXME: draw the rotation too
                 window.draw_line(&f.borrow().anchor1_pos().translate(&na::orig()), &f.borrow().anchor2_pos().translate(&na::orig()), &Pnt3::new(0.0, 1.0, 0.0));
+            },
+            Constraint::Revolute(ref r) => {
+                window.draw_line(&r.borrow().anchor1_pos().translate(&na::orig()), &r.borrow().anchor2_pos().translate(&na::orig()), &Pnt3::new(0.0, 1.0, 0.0));
+            },
+            Constraint::Prismatic(ref p) => {
+                window.draw_line(&p.borrow().anchor1_pos().translate(&na::orig()), &p.borrow().anchor2_pos().translate(&na::orig()), &Pnt3::new(0.0, 1.0, 0.0));
+            },
+            Constraint::Spring(ref s) => {
+                let bs = s.borrow();
+                window.draw_line(&bs.anchor1_pos(), &bs.anchor2_pos(), &Pnt3::new(0.0, 1.0, 0.0));
+            },
+            Constraint::Rope(ref r) => {
+                let br = r.borrow();
+                window.draw_line(&br.anchor1_pos(), &br.anchor2_pos(), &Pnt3::new(0.0, 1.0, 0.0));
+            },
+            Constraint::Custom(ref c) => {
+                window.draw_line(&c.borrow().anchor1_pos().translate(&na::orig()), &c.borrow().anchor2_pos().translate(&na::orig()), &Pnt3::new(0.0, 1.0, 0.0));
             }
         }
     }
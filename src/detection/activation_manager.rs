@@ -124,36 +124,45 @@ impl ActivationManager {
         }
 
         // Run the union-find.
+        fn can_join_island(rb: &RigidBody) -> bool {
+            // A moving kinematic body keeps awake everything it touches.
+            rb.can_move() ||
+            (rb.is_kinematic() && (!na::is_zero(&rb.lin_vel()) || !na::is_zero(&rb.ang_vel())))
+        }
+
         fn make_union(b1: &Arc<RWLock<RigidBody>>, b2: &Arc<RWLock<RigidBody>>, ufs: &mut [UnionFindSet]) {
             let rb1 = b1.read();
             let rb2 = b2.read();
 
-            if rb1.can_move() && rb2.can_move() {
+            if can_join_island(&*rb1) && can_join_island(&*rb2) {
                 union_find::union(rb1.index() as uint, rb2.index() as uint, ufs)
             }
         }
 
         world.contact_pairs(|b1, b2, cd| {
-            if cd.num_colls() != 0 {
+            // Sensors do not interact with the bodies they touch.
+            if cd.num_colls() != 0 && !b1.read().is_sensor() && !b2.read().is_sensor() {
                 make_union(b1, b2, self.ufind.as_mut_slice())
             }
         });
 
+        fn make_joint_union<T: Joint<M>, M>(joint: &T, ufs: &mut [UnionFindSet]) {
+            match (joint.anchor1().body.as_ref(), joint.anchor2().body.as_ref()) {
+                (Some(b1), Some(b2)) => make_union(b1, b2, ufs),
+                _ => { }
+            }
+        }
+
         for e in joints.joints().elements().iter() {
             match e.value {
-                Constraint::RBRB(ref b1, ref b2, _) => make_union(b1, b2, self.ufind.as_mut_slice()),
-                Constraint::BallInSocket(ref b)   => {
-                    match (b.read().anchor1().body.as_ref(), b.read().anchor2().body.as_ref()) {
-                        (Some(b1), Some(b2)) => make_union(b1, b2, self.ufind.as_mut_slice()),
-                        _ => { }
-                    }
-                },
-                Constraint::Fixed(ref f)   => {
-                    match (f.read().anchor1().body.as_ref(), f.read().anchor2().body.as_ref()) {
-                        (Some(b1), Some(b2)) => make_union(b1, b2, self.ufind.as_mut_slice()),
-                        _ => { }
-                    }
-                }
+                Constraint::RBRB(ref b1, ref b2, _, _) => make_union(b1, b2, self.ufind.as_mut_slice()),
+                Constraint::BallInSocket(ref b)        => make_joint_union(&*b.read(), self.ufind.as_mut_slice()),
+                Constraint::Fixed(ref f)               => make_joint_union(&*f.read(), self.ufind.as_mut_slice()),
+                Constraint::Revolute(ref r)            => make_joint_union(&*r.read(), self.ufind.as_mut_slice()),
+                Constraint::Prismatic(ref p)           => make_joint_union(&*p.read(), self.ufind.as_mut_slice()),
+                Constraint::Spring(ref s)              => make_joint_union(&*s.read(), self.ufind.as_mut_slice()),
+                Constraint::Rope(ref r)                => make_joint_union(&*r.read(), self.ufind.as_mut_slice()),
+                Constraint::Custom(ref c)              => make_joint_union(&*c.read(), self.ufind.as_mut_slice())
             }
         }
 
@@ -167,10 +176,10 @@ impl ActivationManager {
 
             self.can_deactivate[root] =
                 match b.deactivation_threshold() {
-                    Some(threshold) => {
+                    Some(threshold) if !b.is_kinematic() => {
                         self.can_deactivate[root] && b.activation_state().energy() < threshold
                     },
-                    None => false
+                    _ => false
                 };
         }
 
@@ -2,27 +2,87 @@
 
 use std::sync::Arc;
 use std::sync::RWLock;
+use na;
 use ncollide::geometry::Contact;
 use object::RigidBody;
-use detection::joint::{Fixed, BallInSocket};
+use detection::joint::{Fixed, BallInSocket, Revolute, Prismatic, Spring, Rope, CustomJoint};
 use math::{Scalar, Point, Vect};
+use object::RigidBodyHandle;
+
+/// The physical properties used by the constraint solver to resolve a contact.
+#[deriving(PartialEq, Show, Clone)]
+pub struct ContactProperties {
+    /// The friction coefficient used when the surfaces in contact slide.
+    pub friction:         Scalar,
+    /// The friction coefficient used when the surfaces in contact do not slide.
+    pub static_friction:  Scalar,
+    /// The restitution coefficient.
+    pub restitution:      Scalar,
+    /// The target tangential velocity of the second body relative to the first one at the contact
+    /// point.
+    ///
+    /// This is zero for a regular contact, and can be set to simulate conveyor belts.
+    pub surface_velocity: Vect
+}
+
+impl ContactProperties {
+    /// Creates a new `ContactProperties` with a zero surface velocity.
+    ///
+    /// The static friction coefficient is set to `friction`.
+    pub fn new(friction: Scalar, restitution: Scalar) -> ContactProperties {
+        ContactProperties {
+            friction:         friction.clone(),
+            static_friction:  friction,
+            restitution:      restitution,
+            surface_velocity: na::zero()
+        }
+    }
+}
+
+/// Trait implemented by user-defined hooks that modify the contacts before they are solved.
+pub trait ContactModifier {
+    /// Modifies a contact between two rigid bodies.
+    ///
+    /// Returns `false` if the contact must be discarded.
+    fn modify(&mut self,
+              b1:         &RigidBodyHandle,
+              b2:         &RigidBodyHandle,
+              contact:    &mut Contact<Scalar, Point, Vect>,
+              properties: &mut ContactProperties)
+              -> bool;
+}
 
 /// A constraint between two rigid bodies.
 pub enum Constraint {
     /// A contact.
-    RBRB(Arc<RWLock<RigidBody>>, Arc<RWLock<RigidBody>>, Contact<Scalar, Point, Vect>),
+    RBRB(Arc<RWLock<RigidBody>>, Arc<RWLock<RigidBody>>, Contact<Scalar, Point, Vect>, ContactProperties),
     /// A ball-in-socket joint.
     BallInSocket(Arc<RWLock<BallInSocket>>),
     /// A fixed joint.
     Fixed(Arc<RWLock<Fixed>>),
+    /// A revolute joint.
+    Revolute(Arc<RWLock<Revolute>>),
+    /// A prismatic joint.
+    Prismatic(Arc<RWLock<Prismatic>>),
+    /// A spring.
+    Spring(Arc<RWLock<Spring>>),
+    /// A rope.
+    Rope(Arc<RWLock<Rope>>),
+    /// A user-defined joint.
+    Custom(Arc<RWLock<Box<CustomJoint + Send + Sync>>>),
 }
 
 impl Clone for Constraint {
     fn clone(&self) -> Constraint {
         match *self {
-            Constraint::RBRB(ref a, ref b, ref c) => Constraint::RBRB(a.clone(), b.clone(), c.clone()),
+            Constraint::RBRB(ref a, ref b, ref c, ref p) => Constraint::RBRB(a.clone(), b.clone(), c.clone(), p.clone()),
             Constraint::BallInSocket(ref bis) => Constraint::BallInSocket(bis.clone()),
             Constraint::Fixed(ref f) => Constraint::Fixed(f.clone()),
+            Constraint::Revolute(ref r) => Constraint::Revolute(r.clone()),
+            Constraint::Prismatic(ref p) => Constraint::Prismatic(p.clone()),
+            Constraint::Spring(ref s) => Constraint::Spring(s.clone()),
+            Constraint::Rope(ref r) => Constraint::Rope(r.clone()),
+            Constraint::Custom(ref c) => Constraint::Custom(c.clone()),
         }
     }
 }
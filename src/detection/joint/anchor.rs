@@ -1,10 +1,14 @@
 use std::sync::Arc;
 use std::sync::RWLock;
+use serialize::{Encodable, Encoder, Decodable, Decoder};
 use object::RigidBody;
 use na;
 use math::Point;
 
 /// One of the two end points of a joint.
+///
+/// Only the position of an anchor is serialized: a decoded anchor is attached to the ground.
+#[deriving(Clone)]
 pub struct Anchor<P> {
     /// The body attached to this anchor.
     pub body:     Option<Arc<RWLock<RigidBody>>>,
@@ -25,6 +29,24 @@ impl<P> Anchor<P> {
     }
 }
 
+impl<E, S: Encoder<E>, P: Encodable<S, E>> Encodable<S, E> for Anchor<P> {
+    fn encode(&self, s: &mut S) -> Result<(), E> {
+        s.emit_struct("Anchor", 1, |s| {
+            s.emit_struct_field("position", 0, |s| self.position.encode(s))
+        })
+    }
+}
+
+impl<E, D: Decoder<E>, P: Decodable<D, E>> Decodable<D, E> for Anchor<P> {
+    fn decode(d: &mut D) -> Result<Anchor<P>, E> {
+        d.read_struct("Anchor", 1, |d| {
+            let position = try!(d.read_struct_field("position", 0, |d| Decodable::decode(d)));
+
+            Ok(Anchor::new(None, position))
+        })
+    }
+}
+
 impl<P> Anchor<P> {
     /// The center of mass of the body attached to this anchor.
     ///
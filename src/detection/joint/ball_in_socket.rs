@@ -1,24 +1,89 @@
 use na::Transform;
-use math::Point;
+use na;
+use math::{Scalar, Point, Vect, Orientation};
+use object::RigidBodyHandle;
 use detection::joint::anchor::Anchor;
 use detection::joint::joint::Joint;
+use detection::joint::motor::Motor;
 
 /// A ball-in-socket joint.
 ///
 /// This is usually used to create ragdolls.
+#[deriving(Clone, Encodable, Decodable)]
 pub struct BallInSocket {
-    up_to_date: bool,
-    anchor1:    Anchor<Point>,
-    anchor2:    Anchor<Point>,
+    up_to_date:  bool,
+    anchor1:     Anchor<Point>,
+    anchor2:     Anchor<Point>,
+    cone_limit:  Option<ConeLimit>,
+    twist_limit: Option<TwistLimit>,
+    motors:      Vec<Option<Motor>>
+}
+
+/// Limits the angle between two axes respectively attached to the first and second body of a
+/// `BallInSocket` joint.
+#[deriving(PartialEq, Show, Clone, Encodable, Decodable)]
+pub struct ConeLimit {
+    /// The axis attached to the first body, in its local coordinates.
+    pub axis1:     Vect,
+    /// The axis attached to the second body, in its local coordinates.
+    pub axis2:     Vect,
+    /// The maximum angle between `axis1` and `axis2`.
+    pub max_angle: Scalar
+}
+
+impl ConeLimit {
+    /// Creates a new `ConeLimit`.
+    pub fn new(axis1: Vect, axis2: Vect, max_angle: Scalar) -> ConeLimit {
+        ConeLimit {
+            axis1:     axis1,
+            axis2:     axis2,
+            max_angle: max_angle
+        }
+    }
+}
+
+/// Limits the rotation of the second body of a `BallInSocket` joint around an axis attached to the
+/// first body.
+///
+/// The twist angle is the angle between the reference vectors `ref1` and `ref2`, projected on the
+/// plane orthogonal to `axis1`. This limit is ignored in 2D.
+#[deriving(PartialEq, Show, Clone, Encodable, Decodable)]
+pub struct TwistLimit {
+    /// The twist axis, in the local coordinates of the first body.
+    pub axis1:     Vect,
+    /// The reference vector attached to the first body, in its local coordinates.
+    pub ref1:      Vect,
+    /// The reference vector attached to the second body, in its local coordinates.
+    pub ref2:      Vect,
+    /// The minimum twist angle.
+    pub min_angle: Scalar,
+    /// The maximum twist angle.
+    pub max_angle: Scalar
+}
+
+impl TwistLimit {
+    /// Creates a new `TwistLimit`.
+    pub fn new(axis1: Vect, ref1: Vect, ref2: Vect, min_angle: Scalar, max_angle: Scalar) -> TwistLimit {
+        TwistLimit {
+            axis1:     axis1,
+            ref1:      ref1,
+            ref2:      ref2,
+            min_angle: min_angle,
+            max_angle: max_angle
+        }
+    }
 }
 
 impl BallInSocket {
     /// Creates a ball-in-socket joint.
     pub fn new(anchor1: Anchor<Point>, anchor2: Anchor<Point>) -> BallInSocket {
         BallInSocket {
-            up_to_date: false,
-            anchor1:    anchor1,
-            anchor2:    anchor2
+            up_to_date:  false,
+            anchor1:     anchor1,
+            anchor2:     anchor2,
+            cone_limit:  None,
+            twist_limit: None,
+            motors:      Vec::from_elem(na::dim::<Orientation>(), None)
         }
     }
 
@@ -32,6 +97,66 @@ impl BallInSocket {
         self.up_to_date = true
     }
 
+    /// The limit of the angle between two axes attached to each body.
+    pub fn cone_limit(&self) -> Option<&ConeLimit> {
+        self.cone_limit.as_ref()
+    }
+
+    /// Sets the limit of the angle between two axes attached to each body.
+    ///
+    /// Set this to `None` to remove the limit.
+    pub fn set_cone_limit(&mut self, limit: Option<ConeLimit>) {
+        if limit != self.cone_limit {
+            self.up_to_date = false;
+            self.cone_limit = limit
+        }
+    }
+
+    /// The limit of the rotation of the second body around an axis attached to the first one.
+    pub fn twist_limit(&self) -> Option<&TwistLimit> {
+        self.twist_limit.as_ref()
+    }
+
+    /// Sets the limit of the rotation of the second body around an axis attached to the first one.
+    ///
+    /// Set this to `None` to remove the limit.
+    pub fn set_twist_limit(&mut self, limit: Option<TwistLimit>) {
+        if limit != self.twist_limit {
+            self.up_to_date  = false;
+            self.twist_limit = limit
+        }
+    }
+
+    /// The motor driving the relative angular velocity along the `i`-th canonical rotation axis.
+    ///
+    /// The rotation axes are expressed in the first attached body’s local coordinates.
+    pub fn motor(&self, i: uint) -> Option<&Motor> {
+        self.motors[i].as_ref()
+    }
+
+    /// Sets the motor driving the relative angular velocity along the `i`-th canonical rotation
+    /// axis.
+    ///
+    /// The rotation axes are expressed in the first attached body’s local coordinates. There is
+    /// one axis in 2D, and three in 3D. Set this to `None` to remove the motor.
+    pub fn set_motor(&mut self, i: uint, motor: Option<Motor>) {
+        if motor != self.motors[i] {
+            self.up_to_date = false;
+            self.motors[i]  = motor
+        }
+    }
+
+    /// The number of motors attached to this joint.
+    pub fn num_motors(&self) -> uint {
+        self.motors.iter().filter(|m| m.is_some()).count()
+    }
+
+    #[doc(hidden)]
+    pub fn set_bodies(&mut self, body1: Option<RigidBodyHandle>, body2: Option<RigidBodyHandle>) {
+        self.anchor1.body = body1;
+        self.anchor2.body = body2;
+    }
+
     /// Sets the the second anchor position.
     ///
     /// The position is expressed in the second attached body’s local coordinates.
@@ -0,0 +1,51 @@
+use std::sync::{Arc, RWLock};
+use math::{Scalar, Matrix};
+use detection::joint::anchor::Anchor;
+use detection::joint::joint::Joint;
+use resolution::{VelocityConstraint, CorrectionParameters};
+
+/// Trait implemented by user-defined joints.
+///
+/// A custom joint reports the bodies it links through its anchors, and generates its own velocity
+/// constraints. Once added to the `JointManager`, it is handled by the activation manager and the
+/// constraint solver exactly like the built-in joints. The functions of the
+/// `resolution::joint_equation_helper` module can be used to fill the velocity constraints.
+///
+/// Unlike the built-in joints, modifying a custom joint does not wake up its bodies.
+pub trait CustomJoint: Joint<Matrix> {
+    /// The number of velocity constraints generated by this joint.
+    fn num_equations(&self) -> uint;
+
+    /// Fills the velocity constraints generated by this joint.
+    ///
+    /// `constraints` has exactly `self.num_equations()` elements.
+    fn fill_second_order_equation(&self,
+                                  dt:          Scalar,
+                                  constraints: &mut [VelocityConstraint],
+                                  correction:  &CorrectionParameters);
+}
+
+/// A shared, dynamically typed, user-defined joint.
+pub type CustomJointHandle = Arc<RWLock<Box<CustomJoint + Send + Sync>>>;
+
+impl Joint<Matrix> for Box<CustomJoint + Send + Sync> {
+    #[inline]
+    fn anchor1(&self) -> &Anchor<Matrix> {
+        (**self).anchor1()
+    }
+
+    #[inline]
+    fn anchor2(&self) -> &Anchor<Matrix> {
+        (**self).anchor2()
+    }
+
+    #[inline]
+    fn anchor1_pos(&self) -> Matrix {
+        (**self).anchor1_pos()
+    }
+
+    #[inline]
+    fn anchor2_pos(&self) -> Matrix {
+        (**self).anchor2_pos()
+    }
+}
@@ -1,8 +1,10 @@
 use math::Matrix;
+use object::RigidBodyHandle;
 use detection::joint::anchor::Anchor;
 use detection::joint::joint::Joint;
 
 /// A joint that prevents any relative movement (linear and angular) between two objects.
+#[deriving(Clone, Encodable, Decodable)]
 pub struct Fixed {
     up_to_date: bool,
     anchor1:    Anchor<Matrix>,
@@ -29,6 +31,12 @@ impl Fixed {
         self.up_to_date = true
     }
 
+    #[doc(hidden)]
+    pub fn set_bodies(&mut self, body1: Option<RigidBodyHandle>, body2: Option<RigidBodyHandle>) {
+        self.anchor1.body = body1;
+        self.anchor2.body = body2;
+    }
+
     /// Sets the the second anchor position.
     ///
     /// The position is expressed in the second attached body’s local coordinates.
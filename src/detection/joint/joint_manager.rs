@@ -1,20 +1,121 @@
 use std::sync::RWLock;
 use std::sync::Arc;
-use std::ptr;
+use std::collections::HashMap as StdHashMap;
+use na;
 use ncollide::utils::data::hash_map::HashMap;
 use ncollide::utils::data::hash::UintTWHash;
 use detection::activation_manager::ActivationManager;
 use detection::detector::Detector;
 use detection::joint::ball_in_socket::BallInSocket;
 use detection::joint::fixed::Fixed;
+use detection::joint::revolute::Revolute;
+use detection::joint::prismatic::Prismatic;
+use detection::joint::spring::Spring;
+use detection::joint::rope::Rope;
+use detection::joint::custom_joint::CustomJointHandle;
 use detection::joint::joint::Joint;
 use detection::constraint::Constraint;
-use object::RigidBody;
+use object::{RigidBody, RigidBodyHandle};
+use math::Scalar;
+
+// Evaluates `$e` with `$j` bound to the handle of the joint wrapped by `$constraint`. User-defined
+// joints evaluate `$custom` instead, if it is given.
+macro_rules! with_joint(
+	($constraint: expr, $j: ident => $e: expr, $c: pat => $custom: expr) => (
+		match $constraint {
+			Constraint::BallInSocket(ref $j) => $e,
+			Constraint::Fixed(ref $j)        => $e,
+			Constraint::Revolute(ref $j)     => $e,
+			Constraint::Prismatic(ref $j)    => $e,
+			Constraint::Spring(ref $j)       => $e,
+			Constraint::Rope(ref $j)         => $e,
+			Constraint::Custom($c)           => $custom,
+			Constraint::RBRB(_, _, _, _)     => panic!("Internal error: a contact RBRB is not a joint.")
+		}
+	);
+	($constraint: expr, $j: ident => $e: expr) => (
+		with_joint!($constraint, $j => $e, ref $j => $e)
+	)
+)
+
+/// The joints of a `JointManager`, captured by `JointManager::snapshot`.
+#[deriving(Clone, Encodable, Decodable)]
+pub struct JointManagerSnapshot {
+	joints:  Vec<JointSnapshot>,
+	custom:  Vec<(uint, Option<Scalar>)>,
+	next_id: uint
+}
+
+#[deriving(Clone, Encodable, Decodable)]
+struct JointSnapshot {
+	id:         uint,
+	body1:      Option<uint>,
+	body2:      Option<uint>,
+	threshold:  Option<Scalar>,
+	// The anchors of the saved joint are attached to the ground.
+	parameters: JointParameters
+}
+
+#[deriving(Clone, Encodable, Decodable)]
+enum JointParameters {
+	BallInSocket(BallInSocket),
+	Fixed(Fixed),
+	Revolute(Revolute),
+	Prismatic(Prismatic),
+	Spring(Spring),
+	Rope(Rope)
+}
+
+// The built-in joints, which are saved by value in a snapshot.
+trait SavedJoint: Clone + Send + Sync {
+	fn with_bodies(self, body1: Option<RigidBodyHandle>, body2: Option<RigidBodyHandle>) -> Self;
+	fn parameters(self) -> JointParameters;
+	fn constraint(handle: Arc<RWLock<Self>>) -> Constraint;
+	fn handle(constraint: &Constraint) -> Option<&Arc<RWLock<Self>>>;
+}
+
+macro_rules! saved_joint(
+	($t: ident) => (
+		impl SavedJoint for $t {
+			fn with_bodies(mut self, body1: Option<RigidBodyHandle>, body2: Option<RigidBodyHandle>) -> $t {
+				self.set_bodies(body1, body2);
+				self
+			}
+
+			fn parameters(self) -> JointParameters {
+				JointParameters::$t(self)
+			}
+
+			fn constraint(handle: Arc<RWLock<$t>>) -> Constraint {
+				Constraint::$t(handle)
+			}
+
+			fn handle(constraint: &Constraint) -> Option<&Arc<RWLock<$t>>> {
+				match *constraint {
+					Constraint::$t(ref handle) => Some(handle),
+					_                          => None
+				}
+			}
+		}
+	)
+)
+
+saved_joint!(BallInSocket)
+saved_joint!(Fixed)
+saved_joint!(Revolute)
+saved_joint!(Prismatic)
+saved_joint!(Spring)
+saved_joint!(Rope)
 
 /// Structure that handles creation and removal of joints.
 pub struct JointManager {
 	joints:      HashMap<uint, Constraint, UintTWHash>,
-	body2joints: HashMap<uint, Vec<Constraint>, UintTWHash>
+	ids:         HashMap<uint, uint, UintTWHash>,
+	body2joints: HashMap<uint, Vec<Constraint>, UintTWHash>,
+	loads:       HashMap<uint, Vec<Scalar>, UintTWHash>,
+	thresholds:  HashMap<uint, Scalar, UintTWHash>,
+	broken:      Vec<Constraint>,
+	next_id:     uint
 }
 
 impl JointManager {
@@ -22,7 +123,12 @@ impl JointManager {
 	pub fn new() -> JointManager {
 		JointManager {
 			joints:      HashMap::new(UintTWHash::new()),
-			body2joints: HashMap::new(UintTWHash::new())
+			ids:         HashMap::new(UintTWHash::new()),
+			body2joints: HashMap::new(UintTWHash::new()),
+			loads:       HashMap::new(UintTWHash::new()),
+			thresholds:  HashMap::new(UintTWHash::new()),
+			broken:      Vec::new(),
+			next_id:     0
 		}
 	}
 
@@ -32,47 +138,52 @@ impl JointManager {
 		&self.joints
 	}
 
+	/// The identifier of a joint of this manager.
+	///
+	/// Joints are numbered in the order they are added, starting from 0, and identifiers are never
+	/// reused.
+	#[inline]
+	pub fn joint_id(&self, joint: &Constraint) -> Option<uint> {
+		self.ids.find(&joint_key(joint)).map(|id| *id)
+	}
+
 	/// List of joints attached to a specific body.
 	#[inline]
 	pub fn joints_with_body(&self, body: &Arc<RWLock<RigidBody>>) -> Option<&[Constraint]> {
 		self.body2joints.find(&(body.deref() as *const RWLock<RigidBody> as uint)).map(|v| v.as_slice())
 	}
 
+	/// Joints removed by `remove_broken` since the last call to `clear_broken`.
+	#[inline]
+	pub fn broken_joints(&self) -> &[Constraint] {
+		self.broken.as_slice()
+	}
+
+	/// Empties the list of joints returned by `broken_joints`.
+	#[inline]
+	pub fn clear_broken(&mut self) {
+		self.broken.clear()
+	}
+
 	/// Add a `BallInSocket` joint to this manager.
 	///
 	/// This will force the activation of the two objects attached to the joint.
 	pub fn add_ball_in_socket(&mut self,
 							  joint:      Arc<RWLock<BallInSocket>>,
 							  activation: &mut ActivationManager) {
-		if self.joints.insert(joint.deref() as *const RWLock<BallInSocket> as uint,
-							  Constraint::BallInSocket(joint.clone())) {
-			match joint.read().anchor1().body.as_ref() {
-				Some(b) => {
-					activation.will_activate(b);
-					let js = self.body2joints.find_or_insert_lazy(b.deref() as *const RWLock<RigidBody> as uint,
-																  || Some(Vec::new()));
-					js.unwrap().push(Constraint::BallInSocket(joint.clone()));
-				},
-				_ => { }
-			}
-
-			match joint.read().anchor2().body.as_ref() {
-				Some(b) => {
-					activation.will_activate(b);
-					let js = self.body2joints.find_or_insert_lazy(b.deref() as *const RWLock<RigidBody> as uint,
-																  || Some(Vec::new()));
-					js.unwrap().push(Constraint::BallInSocket(joint.clone()));
-				},
-				_ => { }
-			}
-		}
+		self.add_joint(&joint, Constraint::BallInSocket(joint.clone()), activation)
 	}
 
 	/// Removes a `BallInSocket` joint from this manager.
 	///
 	/// This will force the activation of the two objects attached to the joint.
 	pub fn remove_ball_in_socket(&mut self, joint: &Arc<RWLock<BallInSocket>>, activation: &mut ActivationManager) {
-		if self.joints.remove(&(joint.deref() as *const RWLock<BallInSocket> as uint)) {
+		let key = joint.deref() as *const RWLock<BallInSocket> as uint;
+
+		if self.joints.remove(&key) {
+			self.ids.remove(&key);
+			self.thresholds.remove(&key);
+
 			let _  = joint.read().anchor1().body.as_ref().map(|b| activation.will_activate(b));
 			let _  = joint.read().anchor2().body.as_ref().map(|b| activation.will_activate(b));
 		}
@@ -82,27 +193,234 @@ impl JointManager {
 	///
 	/// This will force the activation of the two objects attached to the joint.
 	pub fn add_fixed(&mut self, joint: Arc<RWLock<Fixed>>, activation: &mut ActivationManager) {
-		if self.joints.insert(joint.deref() as *const RWLock<Fixed> as uint, Constraint::Fixed(joint.clone())) {
-			match joint.read().anchor1().body.as_ref() {
-				Some(b) => {
-					activation.will_activate(b);
-					let js = self.body2joints.find_or_insert_lazy(b.deref() as *const RWLock<RigidBody> as uint,
-																  || Some(Vec::new()));
-					js.unwrap().push(Constraint::Fixed(joint.clone()));
-				},
-				_ => { }
+		self.add_joint(&joint, Constraint::Fixed(joint.clone()), activation)
+	}
+
+	/// Add a `Revolute` joint to this manager.
+	///
+	/// This will force the activation of the two objects attached to the joint.
+	pub fn add_revolute(&mut self, joint: Arc<RWLock<Revolute>>, activation: &mut ActivationManager) {
+		self.add_joint(&joint, Constraint::Revolute(joint.clone()), activation)
+	}
+
+	/// Add a `Prismatic` joint to this manager.
+	///
+	/// This will force the activation of the two objects attached to the joint.
+	pub fn add_prismatic(&mut self, joint: Arc<RWLock<Prismatic>>, activation: &mut ActivationManager) {
+		self.add_joint(&joint, Constraint::Prismatic(joint.clone()), activation)
+	}
+
+	/// Add a `Spring` joint to this manager.
+	///
+	/// This will force the activation of the two objects attached to the joint.
+	pub fn add_spring(&mut self, joint: Arc<RWLock<Spring>>, activation: &mut ActivationManager) {
+		self.add_joint(&joint, Constraint::Spring(joint.clone()), activation)
+	}
+
+	/// Add a `Rope` joint to this manager.
+	///
+	/// This will force the activation of the two objects attached to the joint.
+	pub fn add_rope(&mut self, joint: Arc<RWLock<Rope>>, activation: &mut ActivationManager) {
+		self.add_joint(&joint, Constraint::Rope(joint.clone()), activation)
+	}
+
+	/// Add a user-defined joint to this manager.
+	///
+	/// This will force the activation of the two objects attached to the joint.
+	pub fn add_custom_joint(&mut self, joint: CustomJointHandle, activation: &mut ActivationManager) {
+		self.add_joint(&joint, Constraint::Custom(joint.clone()), activation)
+	}
+
+	/// The maximum impulse a joint of this manager can withstand during one time step before
+	/// breaking.
+	#[inline]
+	pub fn break_threshold<T: Joint<M>+Send+Sync, M>(&self, joint: &Arc<RWLock<T>>) -> Option<Scalar> {
+		self.thresholds.find(&(joint.deref() as *const RWLock<T> as uint)).map(|t| t.clone())
+	}
+
+	/// Sets the maximum impulse a joint of this manager can withstand during one time step before
+	/// breaking.
+	///
+	/// Set this to `None` to make the joint unbreakable, which is the default. This does nothing
+	/// if the joint is not handled by this manager.
+	pub fn set_break_threshold<T: Joint<M>+Send+Sync, M>(&mut self,
+														 joint:     &Arc<RWLock<T>>,
+														 threshold: Option<Scalar>) {
+		let key = joint.deref() as *const RWLock<T> as uint;
+
+		match threshold {
+			Some(threshold) => {
+				if self.joints.find(&key).is_some() {
+					let _ = self.thresholds.insert(key, threshold);
+				}
+			},
+			None => { let _ = self.thresholds.remove(&key); }
+		}
+	}
+
+	fn add_joint<T: Joint<M>+Send+Sync, M>(&mut self,
+										   joint:      &Arc<RWLock<T>>,
+										   constraint: Constraint,
+										   activation: &mut ActivationManager) {
+		let key = joint.deref() as *const RWLock<T> as uint;
+
+		if self.joints.insert(key, constraint.clone()) {
+			let bj = joint.read();
+
+			self.ids.insert(key, self.next_id);
+			self.next_id = self.next_id + 1;
+
+			for b in [ bj.anchor1().body.as_ref(), bj.anchor2().body.as_ref() ].iter() {
+				match *b {
+					Some(b) => {
+						activation.will_activate(b);
+						let js = self.body2joints.find_or_insert_lazy(b.deref() as *const RWLock<RigidBody> as uint,
+																	  || Some(Vec::new()));
+						js.unwrap().push(constraint.clone());
+					},
+					None => { }
+				}
+			}
+		}
+	}
+
+	/// Captures the joints of this manager.
+	///
+	/// The snapshot can be serialized. The attached rigid bodies are identified by their stable id.
+	/// User-defined joints are not serializable, so only their identifier is recorded.
+	pub fn snapshot(&self) -> JointManagerSnapshot {
+		let mut joints = Vec::new();
+		let mut custom = Vec::new();
+
+		for joint in self.joints.elements().iter() {
+			let id        = *self.ids.find(&joint.key).unwrap();
+			let threshold = self.thresholds.find(&joint.key).map(|t| t.clone());
+
+			let ((body1, body2), parameters) = with_joint!(joint.value, j => saved(j), _ => {
+				custom.push((id, threshold));
+				continue;
+			});
+
+			joints.push(JointSnapshot {
+				id:         id,
+				body1:      body1,
+				body2:      body2,
+				threshold:  threshold,
+				parameters: parameters
+			});
+		}
+
+		joints.sort_by(|j1, j2| j1.id.cmp(&j2.id));
+		custom.sort_by(|c1, c2| c1.val0().cmp(&c2.val0()));
+
+		JointManagerSnapshot {
+			joints:  joints,
+			custom:  custom,
+			next_id: self.next_id
+		}
+	}
+
+	/// Restores the joints captured by `snapshot`.
+	///
+	/// The saved joints are matched with the joints of this manager by identifier, and their
+	/// bodies are looked up by stable id in `bodies`. The handles of the joints that still exist
+	/// are updated in-place. The joints removed since the snapshot was taken are created again,
+	/// unless one of their bodies is missing from `bodies`. The joints added since then are
+	/// removed, and user-defined joints are kept as they are.
+	///
+	/// The break thresholds are restored as well.
+	///
+	/// Unlike the `add_*` methods, this does not activate the attached bodies.
+	pub fn restore(&mut self,
+				   snapshot: &JointManagerSnapshot,
+				   bodies:   &HashMap<uint, RigidBodyHandle, UintTWHash>) {
+		let mut current = StdHashMap::new();
+
+		for joint in self.joints.elements().iter() {
+			let _ = current.insert(*self.ids.find(&joint.key).unwrap(), joint.value.clone());
+		}
+
+		self.joints.clear();
+		self.ids.clear();
+		self.body2joints.clear();
+		self.loads.clear();
+		self.thresholds.clear();
+		self.broken.clear();
+
+		for saved in snapshot.joints.iter() {
+			let (body1, body2) = match (find_body(bodies, saved.body1), find_body(bodies, saved.body2)) {
+				(Some(body1), Some(body2)) => (body1, body2),
+				_                          => continue
+			};
+
+			let old = current.get(&saved.id);
+
+			match saved.parameters {
+				JointParameters::BallInSocket(ref j) => self.restore_joint(j, body1, body2, old, saved),
+				JointParameters::Fixed(ref j)        => self.restore_joint(j, body1, body2, old, saved),
+				JointParameters::Revolute(ref j)     => self.restore_joint(j, body1, body2, old, saved),
+				JointParameters::Prismatic(ref j)    => self.restore_joint(j, body1, body2, old, saved),
+				JointParameters::Spring(ref j)       => self.restore_joint(j, body1, body2, old, saved),
+				JointParameters::Rope(ref j)         => self.restore_joint(j, body1, body2, old, saved)
 			}
+		}
 
-			match joint.read().anchor2().body.as_ref() {
-				Some(b) => {
-					activation.will_activate(b);
-					let js = self.body2joints.find_or_insert_lazy(b.deref() as *const RWLock<RigidBody> as uint,
-																  || Some(Vec::new()));
-					js.unwrap().push(Constraint::Fixed(joint.clone()));
+		for &(id, threshold) in snapshot.custom.iter() {
+			match current.get(&id) {
+				Some(&Constraint::Custom(ref c)) => {
+					self.register_joint(c, Constraint::Custom(c.clone()), id);
+					self.set_break_threshold(c, threshold)
 				},
 				_ => { }
 			}
 		}
+
+		self.next_id = snapshot.next_id;
+	}
+
+	fn restore_joint<T: SavedJoint + Joint<M>, M>(&mut self,
+												  joint: &T,
+												  body1: Option<RigidBodyHandle>,
+												  body2: Option<RigidBodyHandle>,
+												  old:   Option<&Constraint>,
+												  saved: &JointSnapshot) {
+		let joint = joint.clone().with_bodies(body1, body2);
+
+		// Write the saved joint into the handle it was saved from, if it still exists.
+		let handle = match old.and_then(|c| SavedJoint::handle(c)) {
+			Some(handle) => {
+				*handle.write() = joint;
+				handle.clone()
+			},
+			None => Arc::new(RWLock::new(joint))
+		};
+
+		self.register_joint(&handle, SavedJoint::constraint(handle.clone()), saved.id);
+		self.set_break_threshold(&handle, saved.threshold)
+	}
+
+	fn register_joint<T: Joint<M>+Send+Sync, M>(&mut self,
+												joint:      &Arc<RWLock<T>>,
+												constraint: Constraint,
+												id:         uint) {
+		let key = joint.deref() as *const RWLock<T> as uint;
+
+		if self.joints.insert(key, constraint.clone()) {
+			let bj = joint.read();
+
+			self.ids.insert(key, id);
+
+			for b in [ bj.anchor1().body.as_ref(), bj.anchor2().body.as_ref() ].iter() {
+				match *b {
+					Some(b) => {
+						let js = self.body2joints.find_or_insert_lazy(b.deref() as *const RWLock<RigidBody> as uint,
+																	  || Some(Vec::new()));
+						js.unwrap().push(constraint.clone());
+					},
+					None => { }
+				}
+			}
+		}
 	}
 
 	/// Removes a joint from this manager.
@@ -111,7 +429,11 @@ impl JointManager {
 	pub fn remove_joint<T: Joint<M>+Send+Sync, M>(&mut self,
 										joint:      &Arc<RWLock<T>>,
 										activation: &mut ActivationManager) {
-		if self.joints.remove(&(joint.deref() as *const RWLock<T> as uint)) {
+		let key = joint.deref() as *const RWLock<T> as uint;
+
+		if self.joints.remove(&key) {
+			self.ids.remove(&key);
+			self.thresholds.remove(&key);
 			self.remove_joint_for_body(joint, joint.read().anchor1().body.as_ref(), activation);
 			self.remove_joint_for_body(joint, joint.read().anchor2().body.as_ref(), activation);
 		}
@@ -129,15 +451,8 @@ impl JointManager {
 					Some(ref mut js) => {
 						let jkey = joint.deref() as *const RWLock<T>;
 						js.retain(|j| {
-							// we do not know the type of the joint, so cast it to uint for
-							// comparison.
-							let id = match *j {
-								Constraint::RBRB(_, _, _) => ptr::null::<uint>() as uint,
-								Constraint::BallInSocket(ref b) => b.deref() as *const RWLock<BallInSocket> as uint,
-								Constraint::Fixed(ref f) => f.deref() as *const RWLock<Fixed> as uint
-							};
-
-							id != jkey as uint
+							// we do not know the type of the joint, so compare the addresses.
+							joint_key(j) != jkey as uint
 						});
 					}
 					None => { }
@@ -171,11 +486,7 @@ impl JointManager {
 					}
 				}
 
-				match *joint {
-					Constraint::BallInSocket(ref bis) => do_remove(self, bis, b, activation),
-					Constraint::Fixed(ref f)          => do_remove(self, f, b, activation),
-					Constraint::RBRB(_, _, _) => panic!("Internal error: a contact RBRB should not be here.")
-				}
+				with_joint!(*joint, j => do_remove(self, j, b, activation))
 			}
 		}
 	}
@@ -184,41 +495,67 @@ impl JointManager {
 	/// Activates the objects that interact with an activated object through a joint.
 	pub fn update(&mut self, activation: &mut ActivationManager) {
 		for joint in self.joints.elements().iter() {
-			match joint.value {
-				Constraint::BallInSocket(ref bis) => {
-					let mut bbis = bis.write();
-					if !bbis.up_to_date() {
-						// the joint has been invalidated by the user: wake up the attached bodies
-						bbis.update();
-						match bbis.anchor1().body {
-							Some(ref b) => activation.will_activate(b),
-							None        => { }
-						}
-						match bbis.anchor2().body {
-							Some(ref b) => activation.will_activate(b),
-							None        => { }
-						}
-					}
-				},
-				Constraint::Fixed(ref f) => { // FIXME: code duplication from BallInSocket
-					let mut bf = f.write();
-					if !bf.up_to_date() {
-						// the joint has been invalidated by the user: wake up the attached bodies
-						bf.update();
-						match bf.anchor1().body {
-							Some(ref b) => activation.will_activate(b),
-							None        => { }
-						}
-						match bf.anchor2().body {
-							Some(ref b) => activation.will_activate(b),
-							None        => { }
-						}
+			with_joint!(joint.value, j => {
+				let mut bj = j.write();
+				if !bj.up_to_date() {
+					// the joint has been invalidated by the user: wake up the attached bodies
+					bj.update();
+					activate_attached_bodies(&*bj, activation);
+				}
+			},
+			// Custom joints do not tell when they are modified.
+			_ => { })
+		}
+	}
+
+	/// Adds the impulses applied by the constraint solver to a joint to the ones recorded since the
+	/// last call to `clear_impulses`.
+	///
+	/// The sum is compared to the joint break threshold by `remove_broken`.
+	pub fn add_impulses(&mut self, joint: &Constraint, impulses: &[Scalar]) {
+		let load = self.loads.find_or_insert_lazy(joint_key(joint), || Some(Vec::new())).unwrap();
+
+		for (i, impulse) in impulses.iter().enumerate() {
+			if i < load.len() {
+				load[i] = load[i] + *impulse;
+			}
+			else {
+				load.push(impulse.clone());
+			}
+		}
+	}
+
+	/// Forgets the impulses recorded by `add_impulses`.
+	#[inline]
+	pub fn clear_impulses(&mut self) {
+		self.loads.clear()
+	}
+
+	/// Removes every joint whose recorded impulses exceed its break threshold.
+	///
+	/// This will force the activation of the objects attached to the broken joints. The removed
+	/// joints are added to the list returned by `broken_joints`.
+	pub fn remove_broken(&mut self, activation: &mut ActivationManager) {
+		let first_broken = self.broken.len();
+
+		for joint in self.joints.elements().iter() {
+			match (self.thresholds.find(&joint.key), self.loads.find(&joint.key)) {
+				(Some(threshold), Some(impulses)) => {
+					let sqimpulse = impulses.iter().fold(na::zero(), |sum: Scalar, i| sum + *i * *i);
+
+					if sqimpulse > *threshold * *threshold {
+						self.broken.push(joint.value.clone())
 					}
 				},
-				Constraint::RBRB(_, _, _) => panic!("Internal error:��a contact RBRB should not be here.")
-
+				_ => { }
 			}
 		}
+
+		for i in range(first_broken, self.broken.len()) {
+			let joint = self.broken[i].clone();
+
+			with_joint!(joint, j => self.remove_joint(j, activation))
+		}
 	}
 
 	/// Collects all the constraints caused by joints.
@@ -228,3 +565,46 @@ impl JointManager {
 		}
 	}
 }
+
+fn joint_key(joint: &Constraint) -> uint {
+	fn key<T>(joint: &Arc<RWLock<T>>) -> uint {
+		joint.deref() as *const RWLock<T> as uint
+	}
+
+	with_joint!(*joint, j => key(j))
+}
+
+// The stable ids of the bodies attached to a joint.
+fn body_ids<T: Joint<M>, M>(joint: &T) -> (Option<uint>, Option<uint>) {
+	(joint.anchor1().body.as_ref().map(|b| b.read().stable_id()),
+	 joint.anchor2().body.as_ref().map(|b| b.read().stable_id()))
+}
+
+// Returns `None` if the body is missing, and `Some(None)` for the ground.
+fn find_body(bodies: &HashMap<uint, RigidBodyHandle, UintTWHash>, id: Option<uint>)
+			 -> Option<Option<RigidBodyHandle>> {
+	match id {
+		Some(id) => bodies.find(&id).map(|b| Some(b.clone())),
+		None     => Some(None)
+	}
+}
+
+// Clones a built-in joint, detached from its bodies, with the stable ids of its bodies.
+fn saved<T: SavedJoint + Joint<M>, M>(joint: &Arc<RWLock<T>>)
+									  -> ((Option<uint>, Option<uint>), JointParameters) {
+	let joint = joint.read().clone();
+	let ids   = body_ids(&joint);
+
+	(ids, joint.with_bodies(None, None).parameters())
+}
+
+fn activate_attached_bodies<T: Joint<M>, M>(joint: &T, activation: &mut ActivationManager) {
+	match joint.anchor1().body {
+		Some(ref b) => activation.will_activate(b),
+		None        => { }
+	}
+	match joint.anchor2().body {
+		Some(ref b) => activation.will_activate(b),
+		None        => { }
+	}
+}
@@ -0,0 +1,24 @@
+use math::Scalar;
+
+/// A motor driving the relative velocity of the two bodies attached to a joint along one of its
+/// axis.
+///
+/// The motor velocity is angular for rotational degrees of freedom and linear for translational
+/// ones.
+#[deriving(PartialEq, Show, Clone, Encodable, Decodable)]
+pub struct Motor {
+    /// The relative velocity the motor tries to reach.
+    pub target_vel: Scalar,
+    /// The maximum force the motor can apply to reach its target velocity.
+    pub max_force:  Scalar
+}
+
+impl Motor {
+    /// Creates a new `Motor`.
+    pub fn new(target_vel: Scalar, max_force: Scalar) -> Motor {
+        Motor {
+            target_vel: target_vel,
+            max_force:  max_force
+        }
+    }
+}
@@ -0,0 +1,177 @@
+use na;
+use math::{Scalar, Matrix, Vect};
+use object::RigidBodyHandle;
+use detection::joint::anchor::Anchor;
+use detection::joint::joint::Joint;
+use detection::joint::motor::Motor;
+
+/// A joint that allows only one relative translational degree of freedom between two objects.
+///
+/// This is usually used to create sliders, pistons, or drawers.
+#[deriving(Clone, Encodable, Decodable)]
+pub struct Prismatic {
+    up_to_date:      bool,
+    anchor1:         Anchor<Matrix>,
+    anchor2:         Anchor<Matrix>,
+    axis:            Vect,
+    min_translation: Option<Scalar>,
+    max_translation: Option<Scalar>,
+    motor:           Option<Motor>
+}
+
+impl Prismatic {
+    /// Creates a new `Prismatic` joint.
+    ///
+    /// The translation axis `axis` is expressed in the first attached body’s local coordinates.
+    pub fn new(anchor1: Anchor<Matrix>, anchor2: Anchor<Matrix>, axis: Vect) -> Prismatic {
+        Prismatic {
+            up_to_date:      false,
+            anchor1:         anchor1,
+            anchor2:         anchor2,
+            axis:            na::normalize(&axis),
+            min_translation: None,
+            max_translation: None,
+            motor:           None
+        }
+    }
+
+    /// Tells if the joint has been modified by the user.
+    pub fn up_to_date(&self) -> bool {
+        self.up_to_date
+    }
+
+    #[doc(hidden)]
+    pub fn update(&mut self) {
+        self.up_to_date = true
+    }
+
+    /// The translation axis, expressed in the first attached body’s local coordinates.
+    pub fn axis(&self) -> Vect {
+        self.axis.clone()
+    }
+
+    /// Sets the translation axis, expressed in the first attached body’s local coordinates.
+    pub fn set_axis(&mut self, axis: Vect) {
+        let axis = na::normalize(&axis);
+
+        if axis != self.axis {
+            self.up_to_date = false;
+            self.axis       = axis
+        }
+    }
+
+    /// The translation axis in global coordinates.
+    pub fn world_axis(&self) -> Vect {
+        match self.anchor1.body {
+            Some(ref b) => na::rotate(b.read().position(), &self.axis),
+            None        => self.axis.clone()
+        }
+    }
+
+    /// The lower limit of the translation of the second anchor relative to the first one.
+    pub fn min_translation(&self) -> Option<Scalar> {
+        self.min_translation.clone()
+    }
+
+    /// Sets the lower limit of the translation of the second anchor relative to the first one.
+    ///
+    /// Set this to `None` to remove the limit.
+    pub fn set_min_translation(&mut self, min_translation: Option<Scalar>) {
+        if min_translation != self.min_translation {
+            self.up_to_date      = false;
+            self.min_translation = min_translation
+        }
+    }
+
+    /// The upper limit of the translation of the second anchor relative to the first one.
+    pub fn max_translation(&self) -> Option<Scalar> {
+        self.max_translation.clone()
+    }
+
+    /// Sets the upper limit of the translation of the second anchor relative to the first one.
+    ///
+    /// Set this to `None` to remove the limit.
+    pub fn set_max_translation(&mut self, max_translation: Option<Scalar>) {
+        if max_translation != self.max_translation {
+            self.up_to_date      = false;
+            self.max_translation = max_translation
+        }
+    }
+
+    /// The motor driving the relative linear velocity along the translation axis.
+    pub fn motor(&self) -> Option<&Motor> {
+        self.motor.as_ref()
+    }
+
+    /// Sets the motor driving the relative linear velocity along the translation axis.
+    ///
+    /// Set this to `None` to remove the motor.
+    pub fn set_motor(&mut self, motor: Option<Motor>) {
+        if motor != self.motor {
+            self.up_to_date = false;
+            self.motor      = motor
+        }
+    }
+
+    #[doc(hidden)]
+    pub fn set_bodies(&mut self, body1: Option<RigidBodyHandle>, body2: Option<RigidBodyHandle>) {
+        self.anchor1.body = body1;
+        self.anchor2.body = body2;
+    }
+
+    /// Sets the first anchor position.
+    ///
+    /// The position is expressed in the first attached body’s local coordinates.
+    pub fn set_local1(&mut self, local1: Matrix) {
+        if local1 != self.anchor1.position {
+            self.up_to_date = false;
+            self.anchor1.position = local1
+        }
+    }
+
+    /// Sets the second anchor position.
+    ///
+    /// The position is expressed in the second attached body’s local coordinates.
+    pub fn set_local2(&mut self, local2: Matrix) {
+        if local2 != self.anchor2.position {
+            self.up_to_date = false;
+            self.anchor2.position = local2
+        }
+    }
+}
+
+impl Joint<Matrix> for Prismatic {
+    /// The first anchor affected by this joint.
+    #[inline]
+    fn anchor1(&self) -> &Anchor<Matrix> {
+        &self.anchor1
+    }
+
+    /// The second anchor affected by this joint.
+    #[inline]
+    fn anchor2(&self) -> &Anchor<Matrix> {
+        &self.anchor2
+    }
+
+    /// The first attach point in global coordinates.
+    #[inline]
+    fn anchor1_pos(&self) -> Matrix {
+        match self.anchor1.body {
+            Some(ref b) => {
+                *b.read().position() * self.anchor1.position
+            },
+            None => self.anchor1.position.clone()
+        }
+    }
+
+    /// The second attach point in global coordinates.
+    #[inline]
+    fn anchor2_pos(&self) -> Matrix {
+        match self.anchor2.body {
+            Some(ref b) => {
+                *b.read().position() * self.anchor2.position
+            },
+            None => self.anchor2.position.clone()
+        }
+    }
+}
@@ -0,0 +1,170 @@
+use na;
+use math::{Scalar, Matrix, Orientation};
+use object::RigidBodyHandle;
+use detection::joint::anchor::Anchor;
+use detection::joint::joint::Joint;
+use detection::joint::motor::Motor;
+
+/// A joint that allows only one relative rotational degree of freedom between two objects.
+///
+/// In 2D, this is a plain pin joint. In 3D, this is a hinge.
+#[deriving(Clone, Encodable, Decodable)]
+pub struct Revolute {
+    up_to_date: bool,
+    anchor1:    Anchor<Matrix>,
+    anchor2:    Anchor<Matrix>,
+    axis:       Orientation,
+    min_angle:  Option<Scalar>,
+    max_angle:  Option<Scalar>,
+    motor:      Option<Motor>
+}
+
+impl Revolute {
+    /// Creates a new `Revolute` joint.
+    ///
+    /// The rotation axis `axis` is expressed in the local frame of both anchors. It is ignored in
+    /// 2D.
+    pub fn new(anchor1: Anchor<Matrix>, anchor2: Anchor<Matrix>, axis: Orientation) -> Revolute {
+        Revolute {
+            up_to_date: false,
+            anchor1:    anchor1,
+            anchor2:    anchor2,
+            axis:       na::normalize(&axis),
+            min_angle:  None,
+            max_angle:  None,
+            motor:      None
+        }
+    }
+
+    /// Tells if the joint has been modified by the user.
+    pub fn up_to_date(&self) -> bool {
+        self.up_to_date
+    }
+
+    #[doc(hidden)]
+    pub fn update(&mut self) {
+        self.up_to_date = true
+    }
+
+    /// The rotation axis, expressed in the local frame of both anchors.
+    pub fn axis(&self) -> Orientation {
+        self.axis.clone()
+    }
+
+    /// Sets the rotation axis, expressed in the local frame of both anchors.
+    pub fn set_axis(&mut self, axis: Orientation) {
+        let axis = na::normalize(&axis);
+
+        if axis != self.axis {
+            self.up_to_date = false;
+            self.axis       = axis
+        }
+    }
+
+    /// The lower limit of the rotation angle of the second anchor relative to the first one.
+    pub fn min_angle(&self) -> Option<Scalar> {
+        self.min_angle.clone()
+    }
+
+    /// Sets the lower limit of the rotation angle of the second anchor relative to the first one.
+    ///
+    /// Set this to `None` to remove the limit.
+    pub fn set_min_angle(&mut self, min_angle: Option<Scalar>) {
+        if min_angle != self.min_angle {
+            self.up_to_date = false;
+            self.min_angle  = min_angle
+        }
+    }
+
+    /// The upper limit of the rotation angle of the second anchor relative to the first one.
+    pub fn max_angle(&self) -> Option<Scalar> {
+        self.max_angle.clone()
+    }
+
+    /// Sets the upper limit of the rotation angle of the second anchor relative to the first one.
+    ///
+    /// Set this to `None` to remove the limit.
+    pub fn set_max_angle(&mut self, max_angle: Option<Scalar>) {
+        if max_angle != self.max_angle {
+            self.up_to_date = false;
+            self.max_angle  = max_angle
+        }
+    }
+
+    /// The motor driving the relative angular velocity along the rotation axis.
+    pub fn motor(&self) -> Option<&Motor> {
+        self.motor.as_ref()
+    }
+
+    /// Sets the motor driving the relative angular velocity along the rotation axis.
+    ///
+    /// Set this to `None` to remove the motor.
+    pub fn set_motor(&mut self, motor: Option<Motor>) {
+        if motor != self.motor {
+            self.up_to_date = false;
+            self.motor      = motor
+        }
+    }
+
+    #[doc(hidden)]
+    pub fn set_bodies(&mut self, body1: Option<RigidBodyHandle>, body2: Option<RigidBodyHandle>) {
+        self.anchor1.body = body1;
+        self.anchor2.body = body2;
+    }
+
+    /// Sets the first anchor position.
+    ///
+    /// The position is expressed in the first attached body’s local coordinates.
+    pub fn set_local1(&mut self, local1: Matrix) {
+        if local1 != self.anchor1.position {
+            self.up_to_date = false;
+            self.anchor1.position = local1
+        }
+    }
+
+    /// Sets the second anchor position.
+    ///
+    /// The position is expressed in the second attached body’s local coordinates.
+    pub fn set_local2(&mut self, local2: Matrix) {
+        if local2 != self.anchor2.position {
+            self.up_to_date = false;
+            self.anchor2.position = local2
+        }
+    }
+}
+
+impl Joint<Matrix> for Revolute {
+    /// The first anchor affected by this joint.
+    #[inline]
+    fn anchor1(&self) -> &Anchor<Matrix> {
+        &self.anchor1
+    }
+
+    /// The second anchor affected by this joint.
+    #[inline]
+    fn anchor2(&self) -> &Anchor<Matrix> {
+        &self.anchor2
+    }
+
+    /// The first attach point in global coordinates.
+    #[inline]
+    fn anchor1_pos(&self) -> Matrix {
+        match self.anchor1.body {
+            Some(ref b) => {
+                *b.read().position() * self.anchor1.position
+            },
+            None => self.anchor1.position.clone()
+        }
+    }
+
+    /// The second attach point in global coordinates.
+    #[inline]
+    fn anchor2_pos(&self) -> Matrix {
+        match self.anchor2.body {
+            Some(ref b) => {
+                *b.read().position() * self.anchor2.position
+            },
+            None => self.anchor2.position.clone()
+        }
+    }
+}
@@ -0,0 +1,114 @@
+use na::Transform;
+use math::{Scalar, Point};
+use object::RigidBodyHandle;
+use detection::joint::anchor::Anchor;
+use detection::joint::joint::Joint;
+
+/// A joint that prevents two attach points from moving further apart than a maximum length.
+///
+/// Unlike a `BallInSocket` chain, this has no effect while the attach points are close enough to
+/// each other. This is usually used to create tethers or grappling hooks.
+#[deriving(Clone, Encodable, Decodable)]
+pub struct Rope {
+    up_to_date: bool,
+    anchor1:    Anchor<Point>,
+    anchor2:    Anchor<Point>,
+    max_length: Scalar
+}
+
+impl Rope {
+    /// Creates a new `Rope` joint.
+    pub fn new(anchor1: Anchor<Point>, anchor2: Anchor<Point>, max_length: Scalar) -> Rope {
+        Rope {
+            up_to_date: false,
+            anchor1:    anchor1,
+            anchor2:    anchor2,
+            max_length: max_length
+        }
+    }
+
+    /// Tells if this joint has been modified by the user.
+    pub fn up_to_date(&self) -> bool {
+        self.up_to_date
+    }
+
+    #[doc(hidden)]
+    pub fn update(&mut self) {
+        self.up_to_date = true
+    }
+
+    /// The maximum distance between the two attach points.
+    pub fn max_length(&self) -> Scalar {
+        self.max_length.clone()
+    }
+
+    /// Sets the maximum distance between the two attach points.
+    pub fn set_max_length(&mut self, max_length: Scalar) {
+        if max_length != self.max_length {
+            self.up_to_date = false;
+            self.max_length = max_length
+        }
+    }
+
+    #[doc(hidden)]
+    pub fn set_bodies(&mut self, body1: Option<RigidBodyHandle>, body2: Option<RigidBodyHandle>) {
+        self.anchor1.body = body1;
+        self.anchor2.body = body2;
+    }
+
+    /// Sets the first anchor position.
+    ///
+    /// The position is expressed in the first attached body’s local coordinates.
+    pub fn set_local1(&mut self, local1: Point) {
+        if local1 != self.anchor1.position {
+            self.up_to_date = false;
+            self.anchor1.position = local1
+        }
+    }
+
+    /// Sets the second anchor position.
+    ///
+    /// The position is expressed in the second attached body’s local coordinates.
+    pub fn set_local2(&mut self, local2: Point) {
+        if local2 != self.anchor2.position {
+            self.up_to_date = false;
+            self.anchor2.position = local2
+        }
+    }
+}
+
+impl Joint<Point> for Rope {
+    /// The first anchor affected by this joint.
+    #[inline]
+    fn anchor1(&self) -> &Anchor<Point> {
+        &self.anchor1
+    }
+
+    /// The second anchor affected by this joint.
+    #[inline]
+    fn anchor2(&self) -> &Anchor<Point> {
+        &self.anchor2
+    }
+
+    /// The first attach point in global coordinates.
+    #[inline]
+    fn anchor1_pos(&self) -> Point {
+        match self.anchor1.body {
+            Some(ref b) => {
+                b.read().position().transform(&self.anchor1.position)
+            },
+            None => self.anchor1.position.clone()
+        }
+    }
+
+    /// The second attach point in global coordinates.
+    #[inline]
+    fn anchor2_pos(&self) -> Point {
+        match self.anchor2.body {
+            Some(ref b) => {
+                b.read().position().transform(&self.anchor2.position)
+            },
+            None => self.anchor2.position.clone()
+        }
+    }
+}
@@ -0,0 +1,155 @@
+use na::Transform;
+use math::{Scalar, Point};
+use object::RigidBodyHandle;
+use detection::joint::anchor::Anchor;
+use detection::joint::joint::Joint;
+
+/// A damped spring between two attach points.
+///
+/// The spring is solved implicitly as a soft distance constraint, so it remains stable even with a
+/// high stiffness. This is usually used to create suspensions.
+#[deriving(Clone, Encodable, Decodable)]
+pub struct Spring {
+    up_to_date:  bool,
+    anchor1:     Anchor<Point>,
+    anchor2:     Anchor<Point>,
+    rest_length: Scalar,
+    stiffness:   Scalar,
+    damping:     Scalar
+}
+
+impl Spring {
+    /// Creates a new `Spring` joint.
+    ///
+    /// # Arguments:
+    /// * `rest_length` - the distance between the two attach points at which the spring applies no
+    /// force.
+    /// * `stiffness`   - the force applied by the spring per unit of elongation.
+    /// * `damping`     - the force applied by the spring per unit of elongation velocity.
+    pub fn new(anchor1:     Anchor<Point>,
+               anchor2:     Anchor<Point>,
+               rest_length: Scalar,
+               stiffness:   Scalar,
+               damping:     Scalar)
+               -> Spring {
+        Spring {
+            up_to_date:  false,
+            anchor1:     anchor1,
+            anchor2:     anchor2,
+            rest_length: rest_length,
+            stiffness:   stiffness,
+            damping:     damping
+        }
+    }
+
+    /// Tells if this joint has been modified by the user.
+    pub fn up_to_date(&self) -> bool {
+        self.up_to_date
+    }
+
+    #[doc(hidden)]
+    pub fn update(&mut self) {
+        self.up_to_date = true
+    }
+
+    /// The distance between the two attach points at which the spring applies no force.
+    pub fn rest_length(&self) -> Scalar {
+        self.rest_length.clone()
+    }
+
+    /// Sets the distance between the two attach points at which the spring applies no force.
+    pub fn set_rest_length(&mut self, rest_length: Scalar) {
+        if rest_length != self.rest_length {
+            self.up_to_date  = false;
+            self.rest_length = rest_length
+        }
+    }
+
+    /// The stiffness of this spring.
+    pub fn stiffness(&self) -> Scalar {
+        self.stiffness.clone()
+    }
+
+    /// Sets the stiffness of this spring.
+    pub fn set_stiffness(&mut self, stiffness: Scalar) {
+        if stiffness != self.stiffness {
+            self.up_to_date = false;
+            self.stiffness  = stiffness
+        }
+    }
+
+    /// The damping coefficient of this spring.
+    pub fn damping(&self) -> Scalar {
+        self.damping.clone()
+    }
+
+    /// Sets the damping coefficient of this spring.
+    pub fn set_damping(&mut self, damping: Scalar) {
+        if damping != self.damping {
+            self.up_to_date = false;
+            self.damping    = damping
+        }
+    }
+
+    #[doc(hidden)]
+    pub fn set_bodies(&mut self, body1: Option<RigidBodyHandle>, body2: Option<RigidBodyHandle>) {
+        self.anchor1.body = body1;
+        self.anchor2.body = body2;
+    }
+
+    /// Sets the first anchor position.
+    ///
+    /// The position is expressed in the first attached body’s local coordinates.
+    pub fn set_local1(&mut self, local1: Point) {
+        if local1 != self.anchor1.position {
+            self.up_to_date = false;
+            self.anchor1.position = local1
+        }
+    }
+
+    /// Sets the second anchor position.
+    ///
+    /// The position is expressed in the second attached body’s local coordinates.
+    pub fn set_local2(&mut self, local2: Point) {
+        if local2 != self.anchor2.position {
+            self.up_to_date = false;
+            self.anchor2.position = local2
+        }
+    }
+}
+
+impl Joint<Point> for Spring {
+    /// The first anchor affected by this joint.
+    #[inline]
+    fn anchor1(&self) -> &Anchor<Point> {
+        &self.anchor1
+    }
+
+    /// The second anchor affected by this joint.
+    #[inline]
+    fn anchor2(&self) -> &Anchor<Point> {
+        &self.anchor2
+    }
+
+    /// The first attach point in global coordinates.
+    #[inline]
+    fn anchor1_pos(&self) -> Point {
+        match self.anchor1.body {
+            Some(ref b) => {
+                b.read().position().transform(&self.anchor1.position)
+            },
+            None => self.anchor1.position.clone()
+        }
+    }
+
+    /// The second attach point in global coordinates.
+    #[inline]
+    fn anchor2_pos(&self) -> Point {
+        match self.anchor2.body {
+            Some(ref b) => {
+                b.read().position().transform(&self.anchor2.position)
+            },
+            None => self.anchor2.position.clone()
+        }
+    }
+}
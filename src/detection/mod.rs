@@ -12,14 +12,26 @@ pub mod detector;
 pub mod joint {
     pub use detection::joint::anchor::Anchor;
     pub use detection::joint::joint::Joint;
-    pub use detection::joint::ball_in_socket::BallInSocket;
+    pub use detection::joint::ball_in_socket::{BallInSocket, ConeLimit, TwistLimit};
     pub use detection::joint::fixed::Fixed;
-    pub use detection::joint::joint_manager::JointManager;
+    pub use detection::joint::revolute::Revolute;
+    pub use detection::joint::prismatic::Prismatic;
+    pub use detection::joint::spring::Spring;
+    pub use detection::joint::rope::Rope;
+    pub use detection::joint::custom_joint::{CustomJoint, CustomJointHandle};
+    pub use detection::joint::motor::Motor;
+    pub use detection::joint::joint_manager::{JointManager, JointManagerSnapshot};
 
     mod joint_manager;
     mod anchor;
     mod ball_in_socket;
     mod fixed;
+    mod revolute;
+    mod prismatic;
+    mod spring;
+    mod rope;
+    mod custom_joint;
+    mod motor;
     // XXX: `pub` due to rust#18241
     #[allow(missing_docs)]
     pub mod joint;
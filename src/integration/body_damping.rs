@@ -1,13 +1,18 @@
 //! Linear and angular velocity damping.
 
+use std::num::Float;
+use na;
 use math::Scalar;
 use integration::Integrator;
 use object::RigidBody;
 
 /// A linear and angular velocity damper.
 ///
-/// This will remove a part of the linear and angular velocity of every rigid body at each frame.
-/// Do not use unless you are experiencing unrealistic vibrations or very unstable joints.
+/// This will remove a part of the linear and angular velocity of every dynamic rigid body at each
+/// frame. The velocities are first multiplied by the global damping coefficients of this damper,
+/// and then by `exp(-damping * dt)` where `damping` is the damping coefficient of the body, so
+/// that the per-body damping does not depend on the time step length.
+#[deriving(Clone)]
 pub struct BodyDamping {
     linear_damping:  Scalar,
     angular_damping: Scalar
@@ -19,23 +24,34 @@ impl BodyDamping {
     /// # Arguments:
     /// * `linear_damping` - coefficient in [0, 1] the linear velocity of each rigid body is
     ///                      multiplied at each update.
-    /// * `linear_damping` - coefficient in [0, 1] the angular velocity of each rigid body is
-    ///                      multiplied at each update.
+    /// * `angular_damping` - coefficient in [0, 1] the angular velocity of each rigid body is
+    ///                       multiplied at each update.
     #[inline]
     pub fn new(linear_damping: Scalar, angular_damping: Scalar) -> BodyDamping {
         BodyDamping {
             linear_damping:  linear_damping,
-            angular_damping: angular_damping,
+            angular_damping: angular_damping
         }
     }
 }
 
 impl Integrator<RigidBody> for BodyDamping {
-    fn update(&mut self, _: Scalar, rb: &mut RigidBody) {
-        let new_lin = rb.lin_vel() * self.linear_damping;
+    fn update(&mut self, dt: Scalar, rb: &mut RigidBody) {
+        if !rb.can_move() {
+            return;
+        }
+
+        let lin_damping = self.linear_damping * (-rb.lin_damping() * dt).exp();
+        let ang_damping = self.angular_damping * (-rb.ang_damping() * dt).exp();
 
-        rb.set_lin_vel(new_lin);
-        let new_ang = rb.ang_vel() * self.angular_damping;
-        rb.set_ang_vel(new_ang);
+        if lin_damping != na::one() {
+            let new_lin = rb.lin_vel() * lin_damping;
+            rb.set_lin_vel(new_lin);
+        }
+
+        if ang_damping != na::one() {
+            let new_ang = rb.ang_vel() * ang_damping;
+            rb.set_ang_vel(new_ang);
+        }
     }
 }
@@ -9,6 +9,7 @@ use integration::euler;
 /// An explicit Euler integrator.
 ///
 /// Do not use this, prefer the `BodySmpEulerIntegrator` instead.
+#[deriving(Clone)]
 pub struct BodyExpEulerIntegrator;
 
 impl BodyExpEulerIntegrator {
@@ -36,5 +37,15 @@ impl Integrator<RigidBody> for BodyExpEulerIntegrator {
             rb.set_lin_vel(lv);
             rb.set_ang_vel(av);
         }
+        else if rb.is_kinematic() {
+            let t = euler::displacement(
+                dt.clone(),
+                rb.position(),
+                rb.center_of_mass(),
+                &rb.lin_vel(),
+                &rb.ang_vel());
+
+            rb.append_transformation(&t);
+        }
     }
 }
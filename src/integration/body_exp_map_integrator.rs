@@ -0,0 +1,54 @@
+//! Exponential-map integrator.
+
+use na::Transformation;
+use object::RigidBody;
+use integration::Integrator;
+use integration::euler;
+use math::Scalar;
+
+/// An integrator moving the bodies with the exponential map of their mean velocities.
+///
+/// The velocities are updated like with the `BodySmpEulerIntegrator`. The bodies are then rotated
+/// with the exponential map of the mean of their angular velocities at the beginning and at the
+/// end of the time step, and translated likewise. Unlike the Euler integrators, this is exact for
+/// constant accelerations around a fixed rotation axis.
+#[deriving(Clone)]
+pub struct BodyExpMapIntegrator;
+
+impl BodyExpMapIntegrator {
+    /// Creates a new `BodyExpMapIntegrator`.
+    #[inline]
+    pub fn new() -> BodyExpMapIntegrator {
+        BodyExpMapIntegrator
+    }
+}
+
+impl Integrator<RigidBody> for BodyExpMapIntegrator {
+    #[inline]
+    fn update(&mut self, dt: Scalar, rb: &mut RigidBody) {
+        if rb.can_move() {
+            let (t, lv, av) = euler::exp_map_integrate(
+                dt.clone(),
+                rb.position(),
+                rb.center_of_mass(),
+                &rb.lin_vel(),
+                &rb.ang_vel(),
+                &rb.lin_acc(),
+                &rb.ang_acc());
+
+            rb.append_transformation(&t);
+            rb.set_lin_vel(lv);
+            rb.set_ang_vel(av);
+        }
+        else if rb.is_kinematic() {
+            let t = euler::displacement(
+                dt.clone(),
+                rb.position(),
+                rb.center_of_mass(),
+                &rb.lin_vel(),
+                &rb.ang_vel());
+
+            rb.append_transformation(&t);
+        }
+    }
+}
@@ -1,10 +1,15 @@
 //! Constant linear and angular force generator.
 
+use ncollide::volumetric::InertiaTensor;
 use math::{Scalar, Vect, Orientation};
 use object::RigidBody;
 use integration::Integrator;
 
 /// A constant linear and angular force generator.
+///
+/// The forces and torques applied by the user to each rigid body are added to the constant
+/// accelerations.
+#[deriving(Clone)]
 pub struct BodyForceGenerator {
     lin_acc: Vect,
     ang_acc: Orientation
@@ -54,7 +59,12 @@ impl BodyForceGenerator {
 impl Integrator<RigidBody> for BodyForceGenerator {
     #[inline]
     fn update(&mut self, _: Scalar, rb: &mut RigidBody) {
-        rb.set_lin_acc(self.lin_acc.clone());
-        rb.set_ang_acc(self.ang_acc.clone());
+        if rb.can_move() {
+            let lin_acc = self.lin_acc + rb.force() * rb.inv_mass();
+            let ang_acc = self.ang_acc + rb.inv_inertia().apply(&rb.torque());
+
+            rb.set_lin_acc(lin_acc);
+            rb.set_ang_acc(ang_acc);
+        }
     }
 }
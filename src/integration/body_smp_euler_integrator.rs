@@ -7,6 +7,7 @@ use integration::euler;
 use math::Scalar;
 
 /// A semi-implicit Euler integrator.
+#[deriving(Clone)]
 pub struct BodySmpEulerIntegrator;
 
 impl BodySmpEulerIntegrator {
@@ -34,5 +35,15 @@ impl Integrator<RigidBody> for BodySmpEulerIntegrator {
             rb.set_lin_vel(lv);
             rb.set_ang_vel(av);
         }
+        else if rb.is_kinematic() {
+            let t = euler::displacement(
+                dt.clone(),
+                rb.position(),
+                rb.center_of_mass(),
+                &rb.lin_vel(),
+                &rb.ang_vel());
+
+            rb.append_transformation(&t);
+        }
     }
 }
@@ -0,0 +1,55 @@
+//! Symplectic integrator taking the gyroscopic torque into account.
+
+use na::Transformation;
+use object::RigidBody;
+use integration::Integrator;
+use integration::euler;
+use math::Scalar;
+
+/// A semi-implicit Euler integrator with an implicit gyroscopic term.
+///
+/// The explicit integrators ignore the gyroscopic torque `ω × Iω`, making objects with an
+/// anisotropic inertia tensor gain energy when they spin fast. This integrator solves for it
+/// implicitly instead. In 2D, it behaves exactly like the `BodySmpEulerIntegrator`.
+#[deriving(Clone)]
+pub struct BodySymplecticIntegrator;
+
+impl BodySymplecticIntegrator {
+    /// Creates a new `BodySymplecticIntegrator`.
+    #[inline]
+    pub fn new() -> BodySymplecticIntegrator {
+        BodySymplecticIntegrator
+    }
+}
+
+impl Integrator<RigidBody> for BodySymplecticIntegrator {
+    #[inline]
+    fn update(&mut self, dt: Scalar, rb: &mut RigidBody) {
+        if rb.can_move() {
+            let av = euler::implicit_gyroscopic_velocity(dt.clone(), rb.inv_inertia(), &rb.ang_vel());
+
+            let (t, lv, av) = euler::semi_implicit_integrate(
+                dt.clone(),
+                rb.position(),
+                rb.center_of_mass(),
+                &rb.lin_vel(),
+                &av,
+                &rb.lin_acc(),
+                &rb.ang_acc());
+
+            rb.append_transformation(&t);
+            rb.set_lin_vel(lv);
+            rb.set_ang_vel(av);
+        }
+        else if rb.is_kinematic() {
+            let t = euler::displacement(
+                dt.clone(),
+                rb.position(),
+                rb.center_of_mass(),
+                &rb.lin_vel(),
+                &rb.ang_vel());
+
+            rb.append_transformation(&t);
+        }
+    }
+}
@@ -2,7 +2,7 @@
 
 use na::{Translation, RotationWithTranslation};
 use na;
-use math::{Scalar, Point, Vect, Orientation, Matrix};
+use math::{Scalar, Point, Vect, Orientation, Matrix, AngularInertia};
 
 /// Explicit Euler integrator.
 pub fn explicit_integrate(dt: Scalar, p: &Matrix, c: &Point, lv: &Vect, av: &Orientation, lf: &Vect, af: &Orientation) -> (Matrix, Vect, Orientation) {
@@ -43,8 +43,56 @@ pub fn semi_implicit_integrate_wo_rotation(dt: Scalar, p: &Point, lv: &Vect, lf:
     )
 }
 
+/// Exponential-map integrator.
+///
+/// The velocities are updated like the semi-implicit Euler integrator, but the object is moved with
+/// the exponential map of the mean of its velocities at the beginning and at the end of the time
+/// step. This is exact for constant accelerations around a fixed rotation axis.
+pub fn exp_map_integrate(dt: Scalar, p: &Matrix, c: &Point, lv: &Vect, av: &Orientation, lf: &Vect, af: &Orientation) -> (Matrix, Vect, Orientation) {
+    let nlv = *lv + *lf * dt;
+    let nav = *av + *af * dt;
+    let half: Scalar = na::cast(0.5f64);
+
+    (
+        displacement(dt.clone(), p, c, &((*lv + nlv) * half), &((*av + nav) * half)),
+        nlv,
+        nav
+    )
+}
+
+/// Applies the gyroscopic torque to the angular velocity `av` using one step of Newton's method.
+///
+/// This solves `I(ω - av) + dt ω × Iω = 0` for `ω`, where `I` is the world-space inertia tensor.
+/// The velocity is left unchanged if the inertia tensor is not invertible.
+#[cfg(feature = "3d")]
+pub fn implicit_gyroscopic_velocity(dt: Scalar, inv_inertia: &AngularInertia, av: &Orientation) -> Orientation {
+    let inertia = match na::inv(inv_inertia) {
+        Some(inertia) => inertia,
+        None          => return av.clone()
+    };
+
+    let iav      = inertia * *av;
+    let residual = na::cross(av, &iav) * dt;
+    let jacobian = inertia + (na::cross_matrix(av) * inertia - na::cross_matrix(&iav)) * dt;
+
+    match na::inv(&jacobian) {
+        Some(inv_jacobian) => *av - inv_jacobian * residual,
+        None               => av.clone()
+    }
+}
+
+/// Applies the gyroscopic torque to the angular velocity `av`.
+///
+/// There is no gyroscopic torque in this dimension so `av` is returned unchanged.
+#[cfg(not(feature = "3d"))]
+pub fn implicit_gyroscopic_velocity(_: Scalar, _: &AngularInertia, av: &Orientation) -> Orientation {
+    av.clone()
+}
+
 /// Computes the transformation matrix required to move an object with a `lin_vel` linear velocity,
 /// a `ang_vil` angular velocity, and a center of mass `center_of_mass`, during the time step `dt`.
+///
+/// The rotation is computed with the exponential map of `ang_vel * dt`.
 pub fn displacement(dt: Scalar, _: &Matrix, center_of_mass: &Point, lin_vel: &Vect, ang_vel: &Orientation) -> Matrix {
     let mut res: Matrix = na::one();
     res.append_rotation_wrt_point(&(*ang_vel * dt), center_of_mass.as_vec());
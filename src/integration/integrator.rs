@@ -9,3 +9,18 @@ pub trait Integrator<O> {
     /// Updates the position and orientation of the object `o` after a time step of `dt`.
     fn update(&mut self, dt: Scalar, o: &mut O);
 }
+
+/// An integrator that can be copied to update several objects concurrently.
+///
+/// This is implemented by every integrator that is `Clone`, `Send` and `Sync`.
+pub trait ParallelIntegrator<O>: Integrator<O> + Send + Sync {
+    /// Creates a copy of this integrator.
+    fn clone_integrator(&self) -> Box<ParallelIntegrator<O> + Send + Sync>;
+}
+
+impl<O, T: Integrator<O> + Clone + Send + Sync> ParallelIntegrator<O> for T {
+    #[inline]
+    fn clone_integrator(&self) -> Box<ParallelIntegrator<O> + Send + Sync> {
+        box self.clone() as Box<ParallelIntegrator<O> + Send + Sync>
+    }
+}
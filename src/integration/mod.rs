@@ -1,8 +1,10 @@
 //! Position and orientation update of rigid bodies.
 
-pub use integration::integrator::Integrator;
+pub use integration::integrator::{Integrator, ParallelIntegrator};
 pub use integration::body_exp_euler_integrator::BodyExpEulerIntegrator;
 pub use integration::body_smp_euler_integrator::BodySmpEulerIntegrator;
+pub use integration::body_exp_map_integrator::BodyExpMapIntegrator;
+pub use integration::body_symplectic_integrator::BodySymplecticIntegrator;
 pub use integration::body_force_generator::BodyForceGenerator;
 pub use integration::body_damping::BodyDamping;
 pub use integration::translational_ccd_motion_clamping::TranslationalCCDMotionClamping;
@@ -11,6 +13,8 @@ pub use integration::translational_ccd_motion_clamping::TranslationalCCDMotionCl
 pub mod integrator;
 mod body_exp_euler_integrator;
 mod body_smp_euler_integrator;
+mod body_exp_map_integrator;
+mod body_symplectic_integrator;
 mod body_force_generator;
 mod body_damping;
 mod translational_ccd_motion_clamping;
@@ -1,4 +1,5 @@
 use std::num::Float;
+use std::collections::HashMap as StdHashMap;
 use na::Translation;
 use na;
 use ncollide::utils::data::has_uid::HasUid;
@@ -54,6 +55,38 @@ impl TranslationalCCDMotionClamping {
         self.objects.remove(&body.uid());
     }
 
+    /// The last known position of each body with continuous collision detection enabled.
+    ///
+    /// Bodies are identified by their stable id.
+    #[doc(hidden)]
+    pub fn last_positions(&self) -> Vec<(uint, Vect, bool)> {
+        self.objects.elements().iter().map(|o| {
+            (o.value.body.read().stable_id(), o.value.last_pos.clone(), o.value.accept_zero)
+        }).collect()
+    }
+
+    /// Sets the last known position of each body with continuous collision detection enabled.
+    #[doc(hidden)]
+    pub fn set_last_positions(&mut self, positions: &[(uint, Vect, bool)]) {
+        let mut saved = StdHashMap::new();
+
+        for &(id, ref last_pos, accept_zero) in positions.iter() {
+            let _ = saved.insert(id, (last_pos.clone(), accept_zero));
+        }
+
+        for o in self.objects.elements_mut().iter_mut() {
+            let id = o.value.body.read().stable_id();
+
+            match saved.get(&id) {
+                Some(&(ref last_pos, accept_zero)) => {
+                    o.value.last_pos    = last_pos.clone();
+                    o.value.accept_zero = accept_zero;
+                },
+                None => { }
+            }
+        }
+    }
+
     /// Update the time of impacts and apply motion clamping when necessary.
     pub fn update(&mut self, cw: &mut RigidBodyCollisionWorld) {
         let mut update_collision_world = false;
@@ -39,7 +39,7 @@ git = "https://github.com/sebcrozet/nphysics"
 ```
 
 ## Features
-- static and dynamic rigid bodies
+- static, kinematic and dynamic rigid bodies
 - common convex primitives: cone, box, ball, cylinder
 - concave geometries build from convex primitives (aka. compound geometries)
 - stable stacking
@@ -48,17 +48,37 @@ git = "https://github.com/sebcrozet/nphysics"
 - swept sphere based continuous collision detection
 - ball-in-socket joint
 - fixed joint
+- revolute joint
+- prismatic joint
+- damped spring
+- rope
+- user-defined joints
+- joint limits
+- joint motors
+- breakable joints
+- polled contact events with impulses
+- shared materials with configurable combine rules
+- sensors
+- force, torque and impulse application
+- per-body linear and angular damping
+- pluggable integrators, including a symplectic one handling gyroscopic torques
+- world snapshot and restore
+- deterministic mode
+- fixed time stepping with pose interpolation
+- substepping
+- parallel integration and parallel resolution of independent islands
+- graph-colored parallel resolution of large islands
+- pluggable constraint solvers and resolution algorithms (PGS, SOR, Jacobi, NNCG)
 
 ## What is missing?
 **nphysics** is a very young library and needs to learn a lot of things to
 become a grown up. Many missing features are because of missing features on
 **ncollide**. Features missing from **nphysics** itself include:
 
-- kinematic bodies
 - efficient signaling system
-- more joints, joint limits, joint motors and breakable joints.
+- more joints.
 - soft-bodies (see https://github.com/natal/roft for a draft)
-- parallel pipeline
+- parallel collision detection
 - GPU-based pipeline
 
 ## Dependencies
@@ -83,6 +103,7 @@ The libraries needed to compile the examples are:
 #![deny(unused_typecasts)]
 #![warn(non_camel_case_types)]
 #![feature(globs)]
+#![feature(macro_rules)]
 #![feature(default_type_params)]
 #![feature(phase)]
 #![doc(html_root_url = "http://nphysics-dev.org/doc")]
@@ -0,0 +1,142 @@
+use std::sync::Arc;
+use std::collections::HashMap;
+use na;
+use detection::constraint::ContactProperties;
+use math::Scalar;
+
+/// Rule used to combine the coefficients of two materials in contact.
+///
+/// If the two materials use different rules, the one with the highest priority is used. The rules
+/// are listed here by increasing priority.
+#[deriving(Show, PartialEq, Clone)]
+pub enum CombineMode {
+	/// The mean of both coefficients.
+	Average,
+	/// The smallest coefficient.
+	Min,
+	/// The product of both coefficients.
+	Multiply,
+	/// The largest coefficient.
+	Max
+}
+
+impl CombineMode {
+	/// Combines two coefficients.
+	pub fn combine(&self, a: Scalar, b: Scalar) -> Scalar {
+		match *self {
+			CombineMode::Average  => (a + b) / na::cast(2.0f64),
+			CombineMode::Min      => if a < b { a } else { b },
+			CombineMode::Multiply => a * b,
+			CombineMode::Max      => if a > b { a } else { b }
+		}
+	}
+
+	/// Selects the rule used for two materials using `self` and `other`.
+	pub fn resolve(&self, other: &CombineMode) -> CombineMode {
+		if self.priority() >= other.priority() { self.clone() } else { other.clone() }
+	}
+
+	fn priority(&self) -> uint {
+		match *self {
+			CombineMode::Average  => 0,
+			CombineMode::Min      => 1,
+			CombineMode::Multiply => 2,
+			CombineMode::Max      => 3
+		}
+	}
+}
+
+/// The surface properties of a rigid body.
+///
+/// A material is usually shared by several rigid bodies.
+#[deriving(Show, PartialEq, Clone)]
+pub struct Material {
+	/// The friction coefficient used when the surfaces in contact do not slide.
+	pub static_friction:     Scalar,
+	/// The friction coefficient used when the surfaces in contact slide.
+	pub dynamic_friction:    Scalar,
+	/// The restitution coefficient.
+	pub restitution:         Scalar,
+	/// The rule used to combine the friction coefficients of two materials.
+	pub friction_combine:    CombineMode,
+	/// The rule used to combine the restitution coefficients of two materials.
+	pub restitution_combine: CombineMode
+}
+
+impl Material {
+	/// Creates a new material combining its coefficients by multiplication.
+	pub fn new(static_friction: Scalar, dynamic_friction: Scalar, restitution: Scalar) -> Material {
+		Material {
+			static_friction:     static_friction,
+			dynamic_friction:    dynamic_friction,
+			restitution:         restitution,
+			friction_combine:    CombineMode::Multiply,
+			restitution_combine: CombineMode::Multiply
+		}
+	}
+
+	/// Computes the properties of a contact between two materials.
+	pub fn combine(&self, other: &Material) -> ContactProperties {
+		let friction_mode    = self.friction_combine.resolve(&other.friction_combine);
+		let restitution_mode = self.restitution_combine.resolve(&other.restitution_combine);
+
+		let mut res = ContactProperties::new(
+			friction_mode.combine(self.dynamic_friction.clone(), other.dynamic_friction.clone()),
+			restitution_mode.combine(self.restitution.clone(), other.restitution.clone()));
+
+		res.static_friction = friction_mode.combine(self.static_friction.clone(), other.static_friction.clone());
+
+		res
+	}
+}
+
+/// A lookup table overriding the properties of contacts between specific pairs of materials.
+///
+/// Materials are identified by the address of their `Arc`, so the same shared instances have to
+/// be given to the rigid bodies and to the table. The table keeps a handle to every material it
+/// references so that their addresses cannot be reused while an entry exists.
+pub struct MaterialTable {
+	pairs: HashMap<(uint, uint), (Arc<Material>, Arc<Material>, Material)>
+}
+
+impl MaterialTable {
+	/// Creates an empty material table.
+	pub fn new() -> MaterialTable {
+		MaterialTable {
+			pairs: HashMap::new()
+		}
+	}
+
+	/// Sets the coefficients used for the contacts between `m1` and `m2`.
+	///
+	/// The combine modes of `coefficients` are ignored. Set this to `None` to combine the two
+	/// materials with their combine modes again.
+	pub fn set_pair(&mut self, m1: &Arc<Material>, m2: &Arc<Material>, coefficients: Option<Material>) {
+		let key = pair_key(m1, m2);
+
+		match coefficients {
+			Some(c) => { let _ = self.pairs.insert(key, (m1.clone(), m2.clone(), c)); },
+			None    => { let _ = self.pairs.remove(&key); }
+		}
+	}
+
+	/// Computes the properties of a contact between `m1` and `m2`.
+	pub fn contact_properties(&self, m1: &Arc<Material>, m2: &Arc<Material>) -> ContactProperties {
+		match self.pairs.get(&pair_key(m1, m2)) {
+			Some(&(_, _, ref c)) => {
+				let mut res = ContactProperties::new(c.dynamic_friction.clone(), c.restitution.clone());
+				res.static_friction = c.static_friction.clone();
+
+				res
+			},
+			None => m1.combine(&**m2)
+		}
+	}
+}
+
+fn pair_key(m1: &Arc<Material>, m2: &Arc<Material>) -> (uint, uint) {
+	let k1 = m1.deref() as *const Material as uint;
+	let k2 = m2.deref() as *const Material as uint;
+
+	if k1 < k2 { (k1, k2) } else { (k2, k1) }
+}
@@ -1,5 +1,7 @@
 //! Rigid bodies.
 
-pub use object::rigid_body::{RigidBody, RigidBodyHandle, ActivationState, RigidBodyState};
+pub use object::rigid_body::{RigidBody, RigidBodyHandle, RigidBodySnapshot, ActivationState, RigidBodyState};
+pub use object::material::{Material, MaterialTable, CombineMode};
 
 mod rigid_body;
+mod material;
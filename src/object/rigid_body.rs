@@ -7,6 +7,7 @@ use ncollide::bounding_volume::{HasBoundingVolume, BoundingVolume, AABB, HasAABB
 use ncollide::shape::Shape;
 use ncollide::volumetric::{InertiaTensor, Volumetric};
 use ncollide::world::CollisionGroups;
+use object::Material;
 use math::{Scalar, Point, Vect, Orientation, Matrix, AngularInertia};
 
 /// A shared, mutable, rigid body.
@@ -19,7 +20,12 @@ pub enum RigidBodyState { // FIXME: rename this to "RigidBodyKind"?
 	/// The rigid body cannot move.
 	Static,
 	/// The rigid body can move.
-	Dynamic
+	Dynamic,
+	/// The rigid body is moved by its velocities only.
+	///
+	/// It is not affected by forces or contacts and pushes dynamic bodies as if it had an infinite
+	/// mass.
+	Kinematic
 }
 
 #[deriving(Show, PartialEq, Clone, Encodable, Decodable)]
@@ -51,6 +57,7 @@ pub struct RigidBody {
 	state:                RigidBodyState,
 	shape:                Arc<Box<Shape<Scalar, Point, Vect, Matrix> + Send + Sync>>,
 	local_to_world:       Matrix,
+	prev_position:        Matrix,
 	lin_vel:              Vect,
 	ang_vel:              Orientation,
 	inv_mass:             Scalar,
@@ -60,15 +67,60 @@ pub struct RigidBody {
 	center_of_mass:       Point,
 	lin_acc:              Vect,
 	ang_acc:              Orientation,
-	restitution:          Scalar,
-	friction:             Scalar,
+	force:                Vect,
+	torque:               Orientation,
+	lin_damping:          Scalar,
+	ang_damping:          Scalar,
+	material:             Arc<Material>,
 	index:                int,
+	stable_id:            uint,
 	activation_state:     ActivationState,
 	sleep_threshold:      Option<Scalar>,
 	lin_acc_scale:        Vect,        // FIXME: find a better way of doing that.
 	ang_acc_scale:        Orientation, // FIXME: find a better way of doing that.
 	margin:               Scalar,
-	collision_groups:     CollisionGroups
+	collision_groups:     CollisionGroups,
+	kinematic_target:     Option<Matrix>,
+	sensor:               bool
+}
+
+/// The state of a rigid body, captured by `RigidBody::snapshot`.
+///
+/// This contains everything but the shape, the material and the collision groups.
+#[deriving(Show, PartialEq, Clone, Encodable, Decodable)]
+pub struct RigidBodySnapshot {
+	state:                RigidBodyState,
+	local_to_world:       Matrix,
+	prev_position:        Matrix,
+	lin_vel:              Vect,
+	ang_vel:              Orientation,
+	inv_mass:             Scalar,
+	ls_inv_inertia:       AngularInertia,
+	inv_inertia:          AngularInertia,
+	ls_center_of_mass:    Point,
+	center_of_mass:       Point,
+	lin_acc:              Vect,
+	ang_acc:              Orientation,
+	force:                Vect,
+	torque:               Orientation,
+	lin_damping:          Scalar,
+	ang_damping:          Scalar,
+	stable_id:            uint,
+	activation_state:     ActivationState,
+	sleep_threshold:      Option<Scalar>,
+	lin_acc_scale:        Vect,
+	ang_acc_scale:        Orientation,
+	margin:               Scalar,
+	kinematic_target:     Option<Matrix>,
+	sensor:               bool
+}
+
+impl RigidBodySnapshot {
+	/// The stable id of the captured rigid body.
+	#[inline]
+	pub fn stable_id(&self) -> uint {
+		self.stable_id
+	}
 }
 
 impl Clone for RigidBody {
@@ -77,6 +129,7 @@ impl Clone for RigidBody {
 			state:             self.state.clone(),
 			shape:             self.shape.clone(),
 			local_to_world:    self.local_to_world.clone(),
+			prev_position:     self.prev_position.clone(),
 			lin_vel:           self.lin_vel.clone(),
 			ang_vel:           self.ang_vel.clone(),
 			inv_mass:          self.inv_mass.clone(),
@@ -86,15 +139,21 @@ impl Clone for RigidBody {
 			center_of_mass:    self.center_of_mass.clone(),
 			lin_acc:           self.lin_acc.clone(),
 			ang_acc:           self.ang_acc.clone(),
-			restitution:       self.restitution.clone(),
-			friction:          self.friction.clone(),
+			force:             self.force.clone(),
+			torque:            self.torque.clone(),
+			lin_damping:       self.lin_damping.clone(),
+			ang_damping:       self.ang_damping.clone(),
+			material:          self.material.clone(),
 			index:             self.index.clone(),
+			stable_id:         self.stable_id,
 			activation_state:  self.activation_state.clone(),
 			sleep_threshold:   self.sleep_threshold.clone(),
 			lin_acc_scale:     self.lin_acc_scale.clone(),
 			ang_acc_scale:     self.ang_acc_scale.clone(),
 			margin:            self.margin.clone(),
-			collision_groups:  self.collision_groups.clone()
+			collision_groups:  self.collision_groups.clone(),
+			kinematic_target:  self.kinematic_target.clone(),
+			sensor:            self.sensor
 		}
 	}
 }
@@ -134,6 +193,40 @@ impl RigidBody {
 		&self.local_to_world
 	}
 
+	/// Gets a reference to this body's transform before the last time step.
+	#[inline]
+	pub fn previous_position(&self) -> &Matrix {
+		&self.prev_position
+	}
+
+	#[doc(hidden)]
+	#[inline]
+	pub fn save_position(&mut self) {
+		self.prev_position = self.local_to_world.clone()
+	}
+
+	/// Interpolates between this body's transform before and after the last time step.
+	///
+	/// The translation is interpolated linearly, and the rotation along the shortest path. An
+	/// `alpha` of zero gives the previous position, and one gives the current position.
+	pub fn interpolated_position(&self, alpha: Scalar) -> Matrix {
+		let prev = &self.prev_position;
+		let curr = &self.local_to_world;
+
+		let delta = match na::inv(prev) {
+			Some(inv_prev) => na::rotation(&(*curr * inv_prev)),
+			None           => na::zero()
+		};
+
+		let mut res = prev.clone();
+
+		res.set_translation(na::zero());
+		res.append_rotation(&(delta * alpha));
+		res.set_translation(prev.translation() + (curr.translation() - prev.translation()) * alpha);
+
+		res
+	}
+
 	/// Gets a reference to this body's shape.
 	#[inline]
 	pub fn shape_ref(&self) -> &(Shape<Scalar, Point, Vect, Matrix> + Send + Sync) {
@@ -164,6 +257,80 @@ impl RigidBody {
 		self.index = id
 	}
 
+	/// The identifier given to this body when it was added to the world.
+	///
+	/// Identifiers are given in insertion order, and do not depend on the body address.
+	#[inline]
+	pub fn stable_id(&self) -> uint {
+		self.stable_id
+	}
+
+	#[doc(hidden)]
+	#[inline]
+	pub fn set_stable_id(&mut self, id: uint) {
+		self.stable_id = id
+	}
+
+	/// Captures the state of this rigid body.
+	pub fn snapshot(&self) -> RigidBodySnapshot {
+		RigidBodySnapshot {
+			state:             self.state.clone(),
+			local_to_world:    self.local_to_world.clone(),
+			prev_position:     self.prev_position.clone(),
+			lin_vel:           self.lin_vel.clone(),
+			ang_vel:           self.ang_vel.clone(),
+			inv_mass:          self.inv_mass.clone(),
+			ls_inv_inertia:    self.ls_inv_inertia.clone(),
+			inv_inertia:       self.inv_inertia.clone(),
+			ls_center_of_mass: self.ls_center_of_mass.clone(),
+			center_of_mass:    self.center_of_mass.clone(),
+			lin_acc:           self.lin_acc.clone(),
+			ang_acc:           self.ang_acc.clone(),
+			force:             self.force.clone(),
+			torque:            self.torque.clone(),
+			lin_damping:       self.lin_damping.clone(),
+			ang_damping:       self.ang_damping.clone(),
+			stable_id:         self.stable_id,
+			activation_state:  self.activation_state.clone(),
+			sleep_threshold:   self.sleep_threshold.clone(),
+			lin_acc_scale:     self.lin_acc_scale.clone(),
+			ang_acc_scale:     self.ang_acc_scale.clone(),
+			margin:            self.margin.clone(),
+			kinematic_target:  self.kinematic_target.clone(),
+			sensor:            self.sensor
+		}
+	}
+
+	/// Restores the state captured by `snapshot`.
+	///
+	/// The shape, material and collision groups of this rigid body are left unchanged.
+	pub fn restore(&mut self, snapshot: &RigidBodySnapshot) {
+		self.state             = snapshot.state.clone();
+		self.local_to_world    = snapshot.local_to_world.clone();
+		self.prev_position     = snapshot.prev_position.clone();
+		self.lin_vel           = snapshot.lin_vel.clone();
+		self.ang_vel           = snapshot.ang_vel.clone();
+		self.inv_mass          = snapshot.inv_mass.clone();
+		self.ls_inv_inertia    = snapshot.ls_inv_inertia.clone();
+		self.inv_inertia       = snapshot.inv_inertia.clone();
+		self.ls_center_of_mass = snapshot.ls_center_of_mass.clone();
+		self.center_of_mass    = snapshot.center_of_mass.clone();
+		self.lin_acc           = snapshot.lin_acc.clone();
+		self.ang_acc           = snapshot.ang_acc.clone();
+		self.force             = snapshot.force.clone();
+		self.torque            = snapshot.torque.clone();
+		self.lin_damping       = snapshot.lin_damping.clone();
+		self.ang_damping       = snapshot.ang_damping.clone();
+		self.stable_id         = snapshot.stable_id;
+		self.activation_state  = snapshot.activation_state.clone();
+		self.sleep_threshold   = snapshot.sleep_threshold.clone();
+		self.lin_acc_scale     = snapshot.lin_acc_scale.clone();
+		self.ang_acc_scale     = snapshot.ang_acc_scale.clone();
+		self.margin            = snapshot.margin.clone();
+		self.kinematic_target  = snapshot.kinematic_target.clone();
+		self.sensor            = snapshot.sensor;
+	}
+
 	/// Gets a reference to this body's center of mass.
 	#[inline]
 	pub fn center_of_mass(&self) -> &Point {
@@ -172,20 +339,32 @@ impl RigidBody {
 
 	/// Gets this body's restitution coefficent.
 	///
-	/// The actual restitution coefficient of a contact is computed averaging the two bodies
-	/// friction coefficient.
+	/// The actual restitution coefficient of a contact is computed by combining the materials of
+	/// the two bodies.
 	#[inline]
 	pub fn restitution(&self) -> Scalar {
-		self.restitution.clone()
+		self.material.restitution.clone()
 	}
 
-	/// Gets this body's friction coefficient.
+	/// Gets this body's dynamic friction coefficient.
 	///
-	/// The actual friction coefficient of a contact is computed averaging the two bodies friction
-	/// coefficient.
+	/// The actual friction coefficient of a contact is computed by combining the materials of the
+	/// two bodies.
 	#[inline]
 	pub fn friction(&self) -> Scalar {
-		self.friction.clone()
+		self.material.dynamic_friction.clone()
+	}
+
+	/// Gets this body's material.
+	#[inline]
+	pub fn material(&self) -> &Arc<Material> {
+		&self.material
+	}
+
+	/// Sets this body's material.
+	#[inline]
+	pub fn set_material(&mut self, material: Arc<Material>) {
+		self.material = material
 	}
 
 	/// Indicates whether or not this rigid body is active.
@@ -257,6 +436,21 @@ impl RigidBody {
 			friction)
 	}
 
+	/// Creates a new rigid body that is moved by its velocities only.
+	///
+	/// A kinematic body is never deactivated, is not affected by forces, and pushes dynamic bodies
+	/// as if it had an infinite mass.
+	pub fn new_kinematic<G>(shape: G, restitution: Scalar, friction: Scalar) -> RigidBody
+		where G: Send + Sync + Shape<Scalar, Point, Vect, Matrix> {
+		let mut res = RigidBody::new_static(shape, restitution, friction);
+
+		res.state            = RigidBodyState::Kinematic;
+		res.activation_state = ActivationState::Active(Bounded::max_value());
+		res.sleep_threshold  = None;
+
+		res
+	}
+
 	/// Creates a new rigid body with a given shape.
 	///
 	/// Use this if the shape is shared by multiple rigid bodies.
@@ -292,6 +486,7 @@ impl RigidBody {
 				state:             state,
 				shape:             shape,
 				local_to_world:    na::one(),
+				prev_position:     na::one(),
 				lin_vel:           na::zero(),
 				ang_vel:           na::zero(),
 				inv_mass:          inv_mass,
@@ -301,15 +496,21 @@ impl RigidBody {
 				center_of_mass:    na::orig(),
 				lin_acc:           na::zero(),
 				ang_acc:           na::zero(),
-				friction:          friction,
-				restitution:       restitution,
+				force:             na::zero(),
+				torque:            na::zero(),
+				lin_damping:       na::zero(),
+				ang_damping:       na::zero(),
+				material:          Arc::new(Material::new(friction.clone(), friction, restitution)),
 				index:             0,
+				stable_id:         0,
 				activation_state:  active,
 				sleep_threshold:   Some(na::cast(0.1f64)),
 				lin_acc_scale:     na::one(),
 				ang_acc_scale:     na::one(),
 				margin:            na::cast(0.04f32), // FIXME: do not hard-code this.
-				collision_groups:  CollisionGroups::new()
+				collision_groups:  CollisionGroups::new(),
+				kinematic_target:  None,
+				sensor:            false
 			};
 
 		res.update_center_of_mass();
@@ -324,7 +525,53 @@ impl RigidBody {
 		&self.collision_groups
 	}
 
-	/// Indicates whether this rigid body is static or dynamic.
+	/// Indicates whether this rigid body is a sensor.
+	///
+	/// A sensor detects the bodies entering and leaving its shape, but generates no contact.
+	#[inline]
+	pub fn is_sensor(&self) -> bool {
+		self.sensor
+	}
+
+	/// Sets whether this rigid body is a sensor.
+	#[inline]
+	pub fn set_sensor(&mut self, sensor: bool) {
+		self.sensor = sensor
+	}
+
+	/// The linear damping coefficient of this rigid body.
+	#[inline]
+	pub fn lin_damping(&self) -> Scalar {
+		self.lin_damping.clone()
+	}
+
+	/// Sets the linear damping coefficient of this rigid body.
+	///
+	/// The linear velocity is multiplied by `exp(-lin_damping * dt)` at each time step.
+	#[inline]
+	pub fn set_lin_damping(&mut self, damping: Scalar) {
+		assert!(damping >= na::zero(), "The damping coefficient must be non-negative.");
+		self.lin_damping = damping
+	}
+
+	/// The angular damping coefficient of this rigid body.
+	#[inline]
+	pub fn ang_damping(&self) -> Scalar {
+		self.ang_damping.clone()
+	}
+
+	/// Sets the angular damping coefficient of this rigid body.
+	///
+	/// The angular velocity is multiplied by `exp(-ang_damping * dt)` at each time step.
+	#[inline]
+	pub fn set_ang_damping(&mut self, damping: Scalar) {
+		assert!(damping >= na::zero(), "The damping coefficient must be non-negative.");
+		self.ang_damping = damping
+	}
+
+	/// Indicates whether this rigid body is dynamic.
+	///
+	/// Static and kinematic bodies are not moved by forces or contacts.
 	#[inline]
 	pub fn can_move(&self) -> bool {
 		match self.state {
@@ -333,6 +580,38 @@ impl RigidBody {
 		}
 	}
 
+	/// Indicates whether this rigid body is kinematic.
+	#[inline]
+	pub fn is_kinematic(&self) -> bool {
+		match self.state {
+			RigidBodyState::Kinematic => true,
+			_ => false
+		}
+	}
+
+	/// Sets the position this kinematic rigid body has to reach at the end of the next time step.
+	///
+	/// The linear and angular velocities of this body will be set accordingly by the physics
+	/// engine. They are kept afterward unless another target, or velocity, is set.
+	#[inline]
+	pub fn set_kinematic_target(&mut self, target: Matrix) {
+		assert!(self.is_kinematic(), "Only kinematic bodies can be given a target position.");
+		self.kinematic_target = Some(target)
+	}
+
+	#[doc(hidden)]
+	pub fn update_kinematic_velocities(&mut self, dt: Scalar) {
+		match self.kinematic_target.take() {
+			Some(target) => {
+				let delta = target * na::inv(&self.local_to_world).expect("A rigid body position must be inversible.");
+
+				self.lin_vel = (delta.transform(&self.center_of_mass) - self.center_of_mass) / dt;
+				self.ang_vel = delta.rotation() / dt;
+			},
+			None => { }
+		}
+	}
+
 	/// Gets the linear acceleraction scale of this rigid body.
 	#[inline]
 	pub fn lin_acc_scale(&self) -> Vect {
@@ -409,6 +688,99 @@ impl RigidBody {
 		self.ang_acc = af * self.ang_acc_scale
 	}
 
+	/// The sum of the forces applied to this rigid body since the last time step.
+	#[inline]
+	pub fn force(&self) -> Vect {
+		self.force.clone()
+	}
+
+	/// The sum of the torques applied to this rigid body since the last time step.
+	#[inline]
+	pub fn torque(&self) -> Orientation {
+		self.torque.clone()
+	}
+
+	/// Applies a force at the center of mass of this rigid body.
+	///
+	/// Forces accumulate until the next time step, and are cleared afterwards.
+	#[inline]
+	pub fn apply_force(&mut self, force: &Vect) {
+		if self.can_move() {
+			self.force = self.force + *force;
+			self.wake_up();
+		}
+	}
+
+	/// Applies a force at a given point of this rigid body.
+	///
+	/// The point is expressed in world coordinates. This generates a torque if the point is not
+	/// the center of mass.
+	#[inline]
+	pub fn apply_force_at_point(&mut self, force: &Vect, point: &Point) {
+		if self.can_move() {
+			let torque: Orientation = na::cross(&(*point - self.center_of_mass), force);
+
+			self.force  = self.force + *force;
+			self.torque = self.torque + torque;
+			self.wake_up();
+		}
+	}
+
+	/// Applies a torque to this rigid body.
+	///
+	/// Torques accumulate until the next time step, and are cleared afterwards.
+	#[inline]
+	pub fn apply_torque(&mut self, torque: &Orientation) {
+		if self.can_move() {
+			self.torque = self.torque + *torque;
+			self.wake_up();
+		}
+	}
+
+	/// Applies an impulse at the center of mass of this rigid body.
+	///
+	/// The linear velocity is modified immediately.
+	#[inline]
+	pub fn apply_impulse(&mut self, impulse: &Vect) {
+		if self.can_move() {
+			self.lin_vel = self.lin_vel + *impulse * self.inv_mass;
+			self.wake_up();
+		}
+	}
+
+	/// Applies an impulse at a given point of this rigid body.
+	///
+	/// The point is expressed in world coordinates. The linear and angular velocities are
+	/// modified immediately.
+	#[inline]
+	pub fn apply_impulse_at_point(&mut self, impulse: &Vect, point: &Point) {
+		if self.can_move() {
+			let ang_impulse: Orientation = na::cross(&(*point - self.center_of_mass), impulse);
+
+			self.lin_vel = self.lin_vel + *impulse * self.inv_mass;
+			self.ang_vel = self.ang_vel + self.inv_inertia.apply(&ang_impulse);
+			self.wake_up();
+		}
+	}
+
+	#[doc(hidden)]
+	#[inline]
+	pub fn clear_forces(&mut self) {
+		self.force  = na::zero();
+		self.torque = na::zero();
+	}
+
+	fn wake_up(&mut self) {
+		if !self.is_active() {
+			let energy = match self.sleep_threshold {
+				Some(threshold) => threshold * na::cast(2.0f64),
+				None            => Bounded::max_value()
+			};
+
+			self.activate(energy)
+		}
+	}
+
 	/// Gets the inverse mass of this rigid body.
 	#[inline]
 	pub fn inv_mass(&self) -> Scalar {
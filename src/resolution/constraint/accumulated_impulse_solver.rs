@@ -1,29 +1,64 @@
+use std::cmp;
 use std::sync::Arc;
 use std::sync::RWLock;
+use std::sync::TaskPool;
+use std::comm::channel;
 // use rand::RngUtil;
 use na::{Translation, Transformation, RotationWithTranslation};
 use na;
 use math::{Scalar, Vect, Orientation, Matrix};
 use detection::constraint::Constraint;
-use detection::joint::Joint;
+use detection::joint::{Joint, Anchor};
 use object::RigidBody;
+use utils::union_find::UnionFindSet;
+use utils::union_find;
 use resolution::constraint::velocity_constraint::VelocityConstraint;
 use resolution::constraint::contact_equation;
 use resolution::constraint::contact_equation::{CorrectionMode, CorrectionParameters};
 use resolution::constraint::ball_in_socket_equation;
 use resolution::constraint::fixed_equation;
-use resolution::solver::Solver;
-use resolution::constraint::projected_gauss_seidel_solver as pgs;
+use resolution::constraint::revolute_equation;
+use resolution::constraint::prismatic_equation;
+use resolution::constraint::spring_equation;
+use resolution::constraint::rope_equation;
+use resolution::solver::{Solver, ContactImpulse, JointImpulse, SolverState};
 use resolution::constraint::projected_gauss_seidel_solver::Velocities;
-use resolution::constraint::impulse_cache::ImpulseCache;
+use resolution::constraint::resolution_algorithm::{ResolutionAlgorithm, ProjectedGaussSeidel};
+use resolution::constraint::impulse_cache::{ImpulseCache, ContactIdentifier};
+use resolution::constraint::joint_impulse_cache::JointImpulseCache;
 
 
-/// Constraint solver using the projected gauss seidel algorithm and warm-starting.
+/// Constraint solver using warm-starting and, by default, the projected gauss seidel algorithm.
+///
+/// The algorithms used by the velocity and position correction passes can be changed with
+/// `set_velocity_algorithm` and `set_position_algorithm`.
+///
+/// The constraints are split into islands of bodies linked by contacts or joints. Each island is
+/// solved independently, and islands with only sleeping bodies are not solved at all. The islands
+/// can be solved concurrently on a pool of threads (see `set_num_threads`). Large islands solved
+/// alone can themselves be solved in parallel (see `set_graph_coloring`).
 pub struct AccumulatedImpulseSolver {
+	cache:            ImpulseCache,
+	joint_cache:      JointImpulseCache,
+	island_solver:    IslandSolver,
+	workers:          Vec<IslandSolver>,
+	contact_impulses: Vec<ContactImpulse>,
+	joint_impulses:   Vec<JointImpulse>,
+	num_threads:      uint,
+	pool:             Option<TaskPool>,
+	graph_coloring:   bool
+}
+
+// Minimum number of velocity constraints of an island for it to be solved with graph coloring.
+static GRAPH_COLORING_THRESHOLD: uint = 256;
+
+/// Solves the constraints of one island at a time.
+struct IslandSolver {
 	correction:              CorrectionParameters,
-	cache:                   ImpulseCache,
 	num_first_order_iter:    uint,
 	num_second_order_iter:   uint,
+	velocity_algorithm:      Arc<Box<ResolutionAlgorithm + Send + Sync>>,
+	position_algorithm:      Arc<Box<ResolutionAlgorithm + Send + Sync>>,
 	restitution_constraints: Vec<VelocityConstraint>,
 	friction_constraints:    Vec<VelocityConstraint>,
 	mj_lambda:               Vec<Velocities>
@@ -38,44 +73,377 @@ impl AccumulatedImpulseSolver {
 			   num_first_order_iter:  uint,
 			   num_second_order_iter: uint)
 			   -> AccumulatedImpulseSolver {
+		let correction = CorrectionParameters {
+			corr_mode:  correction_mode,
+			joint_corr: joint_corr_factor,
+			rest_eps:   rest_eps,
+			slip_eps:   na::cast(0.01f64)
+		};
+
+		let pgs = Arc::new(box ProjectedGaussSeidel::new() as Box<ResolutionAlgorithm + Send + Sync>);
+
 		AccumulatedImpulseSolver {
-			num_first_order_iter:    num_first_order_iter,
-			num_second_order_iter:   num_second_order_iter,
-			restitution_constraints: Vec::new(),
-			friction_constraints:    Vec::new(),
-			mj_lambda:               Vec::new(),
-			cache:                   ImpulseCache::new(step, na::dim::<Vect>()),
+			cache:            ImpulseCache::new(step, na::dim::<Vect>()),
+			joint_cache:      JointImpulseCache::new(),
+			island_solver:    IslandSolver::new(correction, num_first_order_iter, num_second_order_iter,
+												pgs.clone(), pgs),
+			workers:          Vec::new(),
+			contact_impulses: Vec::new(),
+			joint_impulses:   Vec::new(),
+			num_threads:      1,
+			pool:             None,
+			graph_coloring:   false
+		}
+	}
 
-			correction: CorrectionParameters {
-				corr_mode:  correction_mode,
-				joint_corr: joint_corr_factor,
-				rest_eps:   rest_eps
-			}
+	/// The number of threads used to solve independent islands concurrently.
+	#[inline]
+	pub fn num_threads(&self) -> uint {
+		self.num_threads
+	}
+
+	/// Sets the number of threads used to solve independent islands concurrently.
+	///
+	/// With one thread, everything is solved on the calling thread. Unless graph coloring is
+	/// enabled, the results do not depend on the number of threads.
+	pub fn set_num_threads(&mut self, num_threads: uint) {
+		assert!(num_threads > 0, "The number of threads must be at least 1.");
+
+		if num_threads != self.num_threads {
+			self.num_threads = num_threads;
+			self.pool        = if num_threads > 1 { Some(TaskPool::new(num_threads)) } else { None };
 		}
 	}
 
+	/// Whether large islands are solved in parallel.
+	#[inline]
+	pub fn graph_coloring(&self) -> bool {
+		self.graph_coloring
+	}
+
+	/// Enables or disables the parallel resolution of large islands.
+	///
+	/// When enabled, an island with many constraints solved while no other island is being solved
+	/// is given to `ResolutionAlgorithm::solve_parallel`. With the projected gauss seidel
+	/// algorithm, this splits the island into batches of constraints that do not share any body
+	/// using graph coloring, and solves the constraints of each batch concurrently (see
+	/// `colored_projected_gauss_seidel_solve`). This has no effect with only one thread. Disabled
+	/// by default.
+	#[inline]
+	pub fn set_graph_coloring(&mut self, graph_coloring: bool) {
+		self.graph_coloring = graph_coloring
+	}
+
+	/// The algorithm used to solve the velocity constraints.
+	#[inline]
+	pub fn velocity_algorithm(&self) -> &(ResolutionAlgorithm + Send + Sync) {
+		&**self.island_solver.velocity_algorithm
+	}
+
+	/// Sets the algorithm used to solve the velocity constraints.
+	///
+	/// Defaults to `ProjectedGaussSeidel`.
+	#[inline]
+	pub fn set_velocity_algorithm(&mut self, algorithm: Box<ResolutionAlgorithm + Send + Sync>) {
+		self.island_solver.velocity_algorithm = Arc::new(algorithm)
+	}
+
+	/// The algorithm used by the penetration depth correction solver.
+	#[inline]
+	pub fn position_algorithm(&self) -> &(ResolutionAlgorithm + Send + Sync) {
+		&**self.island_solver.position_algorithm
+	}
+
+	/// Sets the algorithm used by the penetration depth correction solver.
+	///
+	/// Defaults to `ProjectedGaussSeidel`.
+	#[inline]
+	pub fn set_position_algorithm(&mut self, algorithm: Box<ResolutionAlgorithm + Send + Sync>) {
+		self.island_solver.position_algorithm = Arc::new(algorithm)
+	}
+
 	/// Gets the number of iteration done by the penetration depth correction solver.
 	#[inline]
 	pub fn num_first_order_iter(&self) -> uint {
-		self.num_first_order_iter
+		self.island_solver.num_first_order_iter
 	}
 
 	/// Sets the number of iteration done by the penetration depth correction solver.
 	#[inline]
 	pub fn set_num_first_order_iter(&mut self, num: uint) {
-		self.num_first_order_iter = num
+		self.island_solver.num_first_order_iter = num
 	}
 
 	/// Gets the number of iteration done by the velocity constraint solver.
 	#[inline]
 	pub fn num_second_order_iter(&self) -> uint {
-		self.num_second_order_iter
+		self.island_solver.num_second_order_iter
 	}
 
 	/// Sets the number of iteration done by the velocity constraint solver.
 	#[inline]
 	pub fn set_num_second_order_iter(&mut self, num: uint) {
-		self.num_second_order_iter = num
+		self.island_solver.num_second_order_iter = num
+	}
+
+	/// The relative tangential velocity under which static friction is used at a contact.
+	#[inline]
+	pub fn static_friction_threshold(&self) -> Scalar {
+		self.island_solver.correction.slip_eps.clone()
+	}
+
+	/// Sets the relative tangential velocity under which static friction is used at a contact.
+	///
+	/// This is independent of the restitution velocity threshold. Defaults to `0.01`.
+	#[inline]
+	pub fn set_static_friction_threshold(&mut self, threshold: Scalar) {
+		assert!(threshold >= na::zero(), "The static friction threshold must be non-negative.");
+		self.island_solver.correction.slip_eps = threshold
+	}
+
+	/// The impulses applied at each contact during the last call to `solve`.
+	#[inline]
+	pub fn contact_impulses(&self) -> &[ContactImpulse] {
+		self.contact_impulses.as_slice()
+	}
+
+	/// The impulses applied to each joint during the last call to `solve`.
+	#[inline]
+	pub fn joint_impulses(&self) -> &[JointImpulse] {
+		self.joint_impulses.as_slice()
+	}
+
+	/// A copy of the impulses used to warm-start the next call to `solve`.
+	///
+	/// The contacts are identified by the stable ids of their rigid bodies, and the joints by the
+	/// ids given to `solve_with_joint_ids`, so the cache does not depend on where the bodies and
+	/// joints are allocated.
+	pub fn cache(&self) -> SolverState {
+		let mut integers = Vec::new();
+		let mut reals    = Vec::new();
+
+		self.cache.export(&mut integers, &mut reals);
+		self.joint_cache.export(&mut integers, &mut reals);
+
+		SolverState::new(integers, reals)
+	}
+
+	/// Replaces the impulses used to warm-start the next call to `solve`.
+	///
+	/// `cache` must have been returned by the `cache` method of an `AccumulatedImpulseSolver`.
+	pub fn set_cache(&mut self, cache: &SolverState) {
+		let mut integers = cache.integers().iter().map(|i| *i);
+		let mut reals    = cache.reals().iter().map(|r| *r);
+
+		self.cache.import(&mut integers, &mut reals);
+		self.joint_cache.import(&mut integers, &mut reals);
+	}
+
+	fn solve_islands(&mut self,
+					 dt:          Scalar,
+					 constraints: &[Constraint],
+					 contacts:    Vec<(ContactIdentifier, uint, uint)>,
+					 joints:      Vec<(uint, uint)>,
+					 bodies:      Vec<Arc<RWLock<RigidBody>>>) {
+		/*
+		 * Two bodies are on the same island if a constraint links them.
+		 */
+		let mut ufind: Vec<UnionFindSet> = range(0u, bodies.len()).map(|i| UnionFindSet::new(i)).collect();
+
+		for &(_, ci, _) in contacts.iter() {
+			union_constraint_bodies(&constraints[ci], ufind.as_mut_slice());
+		}
+
+		for &(i, _) in joints.iter() {
+			union_constraint_bodies(&constraints[i], ufind.as_mut_slice());
+		}
+
+		let mut root2island = Vec::from_elem(bodies.len(), None);
+		let mut islands     = Vec::new();
+
+		for (i, b) in bodies.into_iter().enumerate() {
+			let root = union_find::find(i, ufind.as_mut_slice());
+
+			if root2island[root].is_none() {
+				root2island[root] = Some(islands.len());
+				islands.push(IslandBuilder::new());
+			}
+
+			islands[root2island[root].unwrap()].bodies.push(b);
+		}
+
+		for contact in contacts.into_iter() {
+			let body = constraint_body(&constraints[contact.val1()]);
+			let root = union_find::find(body, ufind.as_mut_slice());
+
+			islands[root2island[root].unwrap()].contacts.push(contact);
+		}
+
+		for (i, key) in joints.into_iter() {
+			// A joint attached to no dynamic body has nothing to solve.
+			if constraint_has_body(&constraints[i]) {
+				let root = union_find::find(constraint_body(&constraints[i]), ufind.as_mut_slice());
+
+				islands[root2island[root].unwrap()].joints.push((i, key));
+			}
+			else {
+				self.joint_cache.keep(key);
+			}
+		}
+
+		let mut to_solve = Vec::with_capacity(islands.len());
+
+		for builder in islands.into_iter() {
+			if builder.bodies.iter().all(|b| !b.read().is_active()) {
+				// The island is sleeping: keep its impulses for when it wakes up.
+				for &(ref id, _, _) in builder.contacts.iter() {
+					self.cache.keep_impulsions(id);
+				}
+
+				for &(_, key) in builder.joints.iter() {
+					self.joint_cache.keep(key);
+				}
+			}
+			else {
+				to_solve.push(self.build_island(constraints, builder));
+			}
+		}
+
+		/*
+		 * Solve each island independently.
+		 */
+		match self.pool {
+			Some(ref pool) if to_solve.len() > 1 => {
+				let nislands = to_solve.len();
+				let num_jobs = cmp::min(self.num_threads, nislands);
+				let mut jobs = Vec::from_fn(num_jobs, |_| Vec::new());
+
+				for (i, island) in to_solve.into_iter().enumerate() {
+					jobs[i % num_jobs].push((i, island));
+				}
+
+				// Each job solves its islands with its own worker, whose buffers are reused from
+				// one step to the next.
+				while self.workers.len() < num_jobs {
+					self.workers.push(self.island_solver.clone_settings());
+				}
+
+				let (tx, rx) = channel();
+
+				for islands in jobs.into_iter() {
+					let tx         = tx.clone();
+					let mut worker = self.workers.pop().unwrap();
+
+					worker.copy_settings(&self.island_solver);
+
+					pool.execute(proc() {
+						let mut results = Vec::with_capacity(islands.len());
+
+						for &(i, ref island) in islands.iter() {
+							results.push((i, worker.solve(dt.clone(), island, None, 1)));
+						}
+
+						tx.send((worker, results));
+					});
+				}
+
+				// A job that panics drops its sender without sending anything.
+				drop(tx);
+
+				// Merge the results in a deterministic order.
+				let mut results: Vec<Option<IslandResult>> = Vec::from_fn(nislands, |_| None);
+				let mut num_completed = 0u;
+
+				for (worker, job_results) in rx.iter() {
+					self.workers.push(worker);
+
+					for (i, result) in job_results.into_iter() {
+						results[i] = Some(result);
+					}
+
+					num_completed = num_completed + 1;
+				}
+
+				if num_completed != num_jobs {
+					panic!("A job of the parallel island solver failed.")
+				}
+
+				for result in results.into_iter() {
+					merge_result(&mut self.cache, &mut self.joint_cache, &mut self.contact_impulses,
+								 &mut self.joint_impulses, result.unwrap());
+				}
+			},
+			_ => {
+				let pool = if self.graph_coloring { self.pool.as_ref() } else { None };
+
+				for island in to_solve.iter() {
+					let result = self.island_solver.solve(dt.clone(), island, pool, self.num_threads);
+					merge_result(&mut self.cache, &mut self.joint_cache, &mut self.contact_impulses,
+								 &mut self.joint_impulses, result);
+				}
+			}
+		}
+	}
+
+	fn build_island(&self, constraints: &[Constraint], builder: IslandBuilder) -> Island {
+		let mut island_constraints = Vec::with_capacity(builder.contacts.len() + builder.joints.len());
+		let mut contacts           = Vec::with_capacity(builder.contacts.len());
+		let mut joints             = Vec::with_capacity(builder.joints.len());
+
+		for (id, ci, imp) in builder.contacts.into_iter() {
+			island_constraints.push(constraints[ci].clone());
+			contacts.push((id, ci, self.cache.impulsions_at(imp).to_vec()));
+		}
+
+		for (i, key) in builder.joints.into_iter() {
+			island_constraints.push(constraints[i].clone());
+			joints.push((i, key, self.joint_cache.impulsions(key)));
+		}
+
+		Island {
+			bodies:         builder.bodies,
+			constraints:    island_constraints,
+			contacts:       contacts,
+			joints:         joints
+		}
+	}
+}
+
+impl IslandSolver {
+	fn new(correction:            CorrectionParameters,
+		   num_first_order_iter:  uint,
+		   num_second_order_iter: uint,
+		   velocity_algorithm:    Arc<Box<ResolutionAlgorithm + Send + Sync>>,
+		   position_algorithm:    Arc<Box<ResolutionAlgorithm + Send + Sync>>)
+		   -> IslandSolver {
+		IslandSolver {
+			correction:              correction,
+			num_first_order_iter:    num_first_order_iter,
+			num_second_order_iter:   num_second_order_iter,
+			velocity_algorithm:      velocity_algorithm,
+			position_algorithm:      position_algorithm,
+			restitution_constraints: Vec::new(),
+			friction_constraints:    Vec::new(),
+			mj_lambda:               Vec::new()
+		}
+	}
+
+	// Creates a solver with the same settings as `self`, but its own buffers.
+	fn clone_settings(&self) -> IslandSolver {
+		IslandSolver::new(self.correction.clone(),
+						  self.num_first_order_iter,
+						  self.num_second_order_iter,
+						  self.velocity_algorithm.clone(),
+						  self.position_algorithm.clone())
+	}
+
+	// Copies the settings of `other`, keeping the buffers of `self`.
+	fn copy_settings(&mut self, other: &IslandSolver) {
+		self.correction            = other.correction.clone();
+		self.num_first_order_iter  = other.num_first_order_iter;
+		self.num_second_order_iter = other.num_second_order_iter;
+		self.velocity_algorithm    = other.velocity_algorithm.clone();
+		self.position_algorithm    = other.position_algorithm.clone();
 	}
 
 	fn resize_buffers(&mut self, num_restitution_equations: uint, num_friction_equations: uint) {
@@ -88,24 +456,47 @@ impl AccumulatedImpulseSolver {
 					  VelocityConstraint::new());
 	}
 
-	fn do_solve(&mut self,
-				dt:          Scalar,
-				constraints: &[Constraint],
-				joints:      &[uint],
-				bodies:      &[Arc<RWLock<RigidBody>>]) {
-		let num_friction_equations    = (na::dim::<Vect>() - 1) * self.cache.len();
-		let num_restitution_equations = self.cache.len();
+	fn solve(&mut self, dt: Scalar, island: &Island, pool: Option<&TaskPool>, num_threads: uint)
+			 -> IslandResult {
+		let constraints = island.constraints.as_slice();
+		let contacts    = island.contacts.as_slice();
+		let bodies      = island.bodies.as_slice();
+		// The joints are stored after the contacts.
+		let joints: Vec<uint> = range(contacts.len(), constraints.len()).collect();
+		let mut result  = IslandResult::new();
+
+		for (i, b) in bodies.iter().enumerate() {
+			b.write().set_index(i as int);
+		}
+
+		let num_friction_equations    = (na::dim::<Vect>() - 1) * contacts.len();
+		let num_restitution_equations = contacts.len();
 		let mut num_joint_equations = 0;
 
 		for i in joints.iter() {
 			match constraints[*i] {
-				Constraint::BallInSocket(_) => {
-					num_joint_equations = num_joint_equations + na::dim::<Vect>()
+				Constraint::BallInSocket(ref bis) => {
+					num_joint_equations = num_joint_equations + ball_in_socket_equation::num_equations(bis.read().deref())
 				},
 				Constraint::Fixed(_) => {
 					num_joint_equations = num_joint_equations + na::dim::<Vect>() + na::dim::<Orientation>()
 				},
-				Constraint::RBRB(_, _, _) => { }
+				Constraint::Revolute(ref r) => {
+					num_joint_equations = num_joint_equations + revolute_equation::num_equations(r.read().deref())
+				},
+				Constraint::Prismatic(ref p) => {
+					num_joint_equations = num_joint_equations + prismatic_equation::num_equations(p.read().deref())
+				},
+				Constraint::Spring(ref s) => {
+					num_joint_equations = num_joint_equations + spring_equation::num_equations(s.read().deref())
+				},
+				Constraint::Rope(ref r) => {
+					num_joint_equations = num_joint_equations + rope_equation::num_equations(r.read().deref())
+				},
+				Constraint::Custom(ref c) => {
+					num_joint_equations = num_joint_equations + c.read().num_equations()
+				},
+				Constraint::RBRB(_, _, _, _) => { }
 			}
 		}
 
@@ -113,18 +504,19 @@ impl AccumulatedImpulseSolver {
 
 		let mut friction_offset = 0;
 
-		for (i, (_, &(ci, imp))) in self.cache.hash().iter().enumerate() {
-			match constraints[ci] {
-				Constraint::RBRB(ref rb1, ref rb2, ref c) => {
+		for (i, &(_, _, ref imps)) in contacts.iter().enumerate() {
+			match constraints[i] {
+				Constraint::RBRB(ref rb1, ref rb2, ref c, ref p) => {
 					contact_equation::fill_second_order_equation(
 						dt.clone(),
 						c,
+						p,
 						rb1.read().deref(), rb2.read().deref(),
 						&mut self.restitution_constraints[i],
 						i,
 						self.friction_constraints.as_mut_slice(),
 						friction_offset,
-						self.cache.impulsions_at(imp),
+						imps.as_slice(),
 						&self.correction);
 				},
 				_ => { }
@@ -133,19 +525,25 @@ impl AccumulatedImpulseSolver {
 			friction_offset = friction_offset + na::dim::<Vect>() - 1;
 		}
 
+		// FIXME: avoid allocation
+		let mut joint_ranges = Vec::with_capacity(joints.len());
 		let mut joint_offset = num_restitution_equations;
 		for i in joints.iter() {
 			let nconstraints = self.restitution_constraints.len();
+			let first        = joint_offset;
+
 			match constraints[*i] {
 				Constraint::BallInSocket(ref bis) => {
+					let bbis = bis.read();
+
 					ball_in_socket_equation::fill_second_order_equation(
 						dt.clone(),
-						bis.read().deref(),
+						bbis.deref(),
 						self.restitution_constraints.slice_mut(joint_offset, nconstraints), // XXX
 						&self.correction
 					);
 
-					joint_offset = joint_offset + na::dim::<Vect>();
+					joint_offset = joint_offset + ball_in_socket_equation::num_equations(bbis.deref());
 				},
 				Constraint::Fixed(ref f) => {
 					fixed_equation::fill_second_order_equation(
@@ -157,20 +555,127 @@ impl AccumulatedImpulseSolver {
 
 					joint_offset = joint_offset + na::dim::<Vect>() + na::dim::<Orientation>();
 				},
-				Constraint::RBRB(_, _, _) => { }
+				Constraint::Revolute(ref r) => {
+					let br = r.read();
+
+					revolute_equation::fill_second_order_equation(
+						dt.clone(),
+						br.deref(),
+						self.restitution_constraints.slice_mut(joint_offset, nconstraints), // XXX
+						&self.correction
+					);
+
+					joint_offset = joint_offset + revolute_equation::num_equations(br.deref());
+				},
+				Constraint::Prismatic(ref p) => {
+					let bp = p.read();
+
+					prismatic_equation::fill_second_order_equation(
+						dt.clone(),
+						bp.deref(),
+						self.restitution_constraints.slice_mut(joint_offset, nconstraints), // XXX
+						&self.correction
+					);
+
+					joint_offset = joint_offset + prismatic_equation::num_equations(bp.deref());
+				},
+				Constraint::Spring(ref s) => {
+					let bs = s.read();
+
+					spring_equation::fill_second_order_equation(
+						dt.clone(),
+						bs.deref(),
+						self.restitution_constraints.slice_mut(joint_offset, nconstraints) // XXX
+					);
+
+					joint_offset = joint_offset + spring_equation::num_equations(bs.deref());
+				},
+				Constraint::Rope(ref r) => {
+					let br = r.read();
+
+					rope_equation::fill_second_order_equation(
+						dt.clone(),
+						br.deref(),
+						self.restitution_constraints.slice_mut(joint_offset, nconstraints), // XXX
+						&self.correction
+					);
+
+					joint_offset = joint_offset + rope_equation::num_equations(br.deref());
+				},
+				Constraint::Custom(ref c) => {
+					let bc    = c.read();
+					let neqs  = bc.num_equations();
+
+					bc.fill_second_order_equation(
+						dt.clone(),
+						self.restitution_constraints.slice_mut(joint_offset, joint_offset + neqs),
+						&self.correction
+					);
+
+					joint_offset = joint_offset + neqs;
+				},
+				Constraint::RBRB(_, _, _, _) => { }
+			}
+
+			joint_ranges.push((*i, first, joint_offset));
+		}
+
+		/*
+		 * Warm-start the joints with the impulses they applied during the last step.
+		 */
+		for &(i, first, last) in joint_ranges.iter() {
+			let (_, _, ref imps) = island.joints[i - contacts.len()];
+
+			for (c, imp) in self.restitution_constraints.slice_mut(first, last).iter_mut().zip(imps.iter()) {
+				c.impulse = na::clamp(imp.clone(), c.lobound.clone(), c.hibound.clone());
 			}
 		}
 
 		resize_buffer(&mut self.mj_lambda, bodies.len(), Velocities::new());
 
-		// FIXME: parametrize by the resolution algorithm?
-		pgs::projected_gauss_seidel_solve(
+		solve_velocities(
+			&**self.velocity_algorithm,
 			self.restitution_constraints.as_mut_slice(),
 			self.friction_constraints.as_mut_slice(),
 			self.mj_lambda.as_mut_slice(),
 			bodies.len(),
 			self.num_second_order_iter,
-			false);
+			false,
+			pool,
+			num_threads);
+
+		/*
+		 * Record the impulses applied at each contact.
+		 */
+		for (i, &(_, ci, _)) in contacts.iter().enumerate() {
+			let mut friction: Vect = na::zero();
+
+			for j in range(0u, na::dim::<Vect>() - 1) {
+				let ref fc = self.friction_constraints[i * (na::dim::<Vect>() - 1) + j];
+				friction = friction + fc.normal * fc.impulse;
+			}
+
+			result.contact_impulses.push(ContactImpulse {
+				constraint: ci,
+				normal:     self.restitution_constraints[i].impulse.clone(),
+				friction:   friction
+			});
+		}
+
+		/*
+		 * Record and cache the impulses applied to each joint.
+		 */
+		for &(i, first, last) in joint_ranges.iter() {
+			let impulses        = self.restitution_constraints.slice(first, last);
+			let (joint, key, _) = island.joints[i - contacts.len()];
+
+			result.joint_cache.push((key, impulses.iter().map(|c| c.impulse * na::cast(0.85f64)).collect()));
+
+			result.joint_impulses.push(JointImpulse {
+				constraint: joint,
+				impulses:   impulses.iter().map(|c| c.impulse.clone()).collect()
+			});
+		}
 
 		// FIXME: this is _so_ ugly!
 		self.resize_buffers(num_restitution_equations, num_friction_equations);
@@ -186,28 +691,25 @@ impl AccumulatedImpulseSolver {
 			rb.set_ang_vel(curr_ang_vel + self.mj_lambda[i as uint].av);
 		}
 
-		for (i, dv) in self.restitution_constraints.iter().enumerate() {
-			let imps = self.cache.push_impulsions();
-			imps[0]  = dv.impulse * na::cast(0.85f64);
+		for (i, &(ref id, _, _)) in contacts.iter().enumerate() {
+			let mut imps = Vec::with_capacity(na::dim::<Vect>());
+			imps.push(self.restitution_constraints[i].impulse * na::cast(0.85f64));
 
 			for j in range(0u, na::dim::<Vect>() - 1) {
 				let ref fc = self.friction_constraints[i * (na::dim::<Vect>() - 1) + j];
-				imps[1 + j] = fc.impulse * na::cast(0.85f64);
+				imps.push(fc.impulse * na::cast(0.85f64));
 			}
-		}
 
-		let offset = self.cache.reserved_impulse_offset();
-		for (i, (_, kv)) in self.cache.hash_mut().iter_mut().enumerate() {
-			*kv = (kv.val0(), offset + i * na::dim::<Vect>());
+			result.contact_cache.push((id.clone(), imps));
 		}
 
 		/*
 		 * first order resolution
 		 */
 		let needs_correction = !na::is_zero(&self.correction.corr_mode.pos_corr_factor()) &&
-			constraints.iter().any(|constraint| {
+			constraints.slice_to(contacts.len()).iter().any(|constraint| {
 			match *constraint {
-				Constraint::RBRB(_, _, ref c) =>
+				Constraint::RBRB(_, _, ref c, _) =>
 					c.depth >= self.correction.corr_mode.min_depth_for_pos_corr(),
 				_ => false // no first order resolution for joints
 			}
@@ -216,9 +718,9 @@ impl AccumulatedImpulseSolver {
 		if needs_correction {
 			self.resize_buffers(num_restitution_equations, num_friction_equations);
 
-			for (i, (_, &(ci, _))) in self.cache.hash().iter().enumerate() {
-				match constraints[ci] {
-					Constraint::RBRB(_, _, ref c) => {
+			for i in range(0u, contacts.len()) {
+				match constraints[i] {
+					Constraint::RBRB(_, _, ref c, _) => {
 						contact_equation::reinit_to_first_order_equation(
 							dt.clone(),
 							c,
@@ -229,14 +731,16 @@ impl AccumulatedImpulseSolver {
 				}
 			}
 
-			// FIXME: parametrize by the resolution algorithm?
-			pgs::projected_gauss_seidel_solve(
+			solve_velocities(
+				&**self.position_algorithm,
 				self.restitution_constraints.as_mut_slice(),
 				[].as_mut_slice(),
 				self.mj_lambda.as_mut_slice(),
 				bodies.len(),
 				self.num_first_order_iter,
-				true);
+				true,
+				pool,
+				num_threads);
 
 			for b in bodies.iter() {
 				let mut rb = b.write();
@@ -254,31 +758,46 @@ impl AccumulatedImpulseSolver {
 				rb.append_transformation(&delta);
 			}
 		}
+
+		result
 	}
 }
 
 impl Solver<Constraint> for AccumulatedImpulseSolver {
+	/// Solves the constraints, the joints being identified by their index on `constraints`.
+	///
+	/// Use `solve_with_joint_ids` instead to warm-start the joints reliably when their order may
+	/// change from one step to the next.
 	fn solve(&mut self, dt: Scalar, constraints: &[Constraint]) {
+		let joint_ids: Vec<uint> = constraints.iter().enumerate()
+			.filter(|&(_, c)| match *c { Constraint::RBRB(..) => false, _ => true })
+			.map(|(i, _)| i)
+			.collect();
+
+		self.solve_with_joint_ids(dt, constraints, joint_ids.as_slice())
+	}
+
+	fn solve_with_joint_ids(&mut self, dt: Scalar, constraints: &[Constraint], joint_ids: &[uint]) {
 		// FIXME: bodies index assignment is very ugly
 		let mut bodies = Vec::new();
 
+		self.contact_impulses.clear();
+		self.joint_impulses.clear();
+
 		if constraints.len() != 0 {
 			/*
 			 * Associate the constraints with the cached impulse.
 			 */
 			for (i, cstr) in constraints.iter().enumerate() {
 				match *cstr {
-					Constraint::RBRB(ref a, ref b, ref c) => {
+					Constraint::RBRB(ref a, ref b, ref c, _) => {
 						self.cache.insert(i,
-										  a.deref() as *const RWLock<RigidBody> as uint,
-										  b.deref() as *const RWLock<RigidBody> as uint,
+										  a.read().stable_id(),
+										  b.read().stable_id(),
 										  na::center(&c.world1, &c.world2));
 					},
-					Constraint::BallInSocket(_) => {
-						// XXX: cache for ball in socket?
-					},
-					Constraint::Fixed(_) => {
-						// XXX: cache for fixed?
+					_ => {
+						// joint impulses are cached by `do_solve`.
 					}
 				}
 			}
@@ -291,42 +810,17 @@ impl Solver<Constraint> for AccumulatedImpulseSolver {
 			// of all rigid bodies.
 			for c in constraints.iter() {
 				match *c {
-					Constraint::RBRB(ref a, ref b, _) => {
+					Constraint::RBRB(ref a, ref b, _, _) => {
 						a.write().set_index(-2);
 						b.write().set_index(-2)
 					},
-					Constraint::BallInSocket(ref bis) => {
-						let bbis = bis.read();
-						match bbis.anchor1().body {
-							Some(ref b) => {
-								b.write().set_index(-2)
-							},
-							None    => { }
-						};
-
-						match bbis.anchor2().body {
-							Some(ref b) => {
-								b.write().set_index(-2)
-							},
-							None    => { }
-						}
-					}
-					Constraint::Fixed(ref f) => { // FIXME: code duplication from BallInSocket
-						let bf = f.read();
-						match bf.anchor1().body {
-							Some(ref b) => {
-								b.write().set_index(-2)
-							},
-							None    => { }
-						};
-
-						match bf.anchor2().body {
-							Some(ref b) => {
-								b.write().set_index(-2)
-							},
-							None    => { }
-						}
-					}
+					Constraint::BallInSocket(ref bis) => reset_joint_body_indices(bis.read().deref()),
+					Constraint::Fixed(ref f)          => reset_joint_body_indices(f.read().deref()),
+					Constraint::Revolute(ref r)       => reset_joint_body_indices(r.read().deref()),
+					Constraint::Prismatic(ref p)      => reset_joint_body_indices(p.read().deref()),
+					Constraint::Spring(ref s)         => reset_joint_body_indices(s.read().deref()),
+					Constraint::Rope(ref r)           => reset_joint_body_indices(r.read().deref()),
+					Constraint::Custom(ref c)         => reset_joint_body_indices(c.read().deref())
 				}
 			}
 
@@ -346,49 +840,215 @@ impl Solver<Constraint> for AccumulatedImpulseSolver {
 				}
 			}
 
+			fn set_joint_body_indices<T: Joint<M>, M>(joint: &T, bodies: &mut Vec<Arc<RWLock<RigidBody>>>, id: &mut int) {
+				match joint.anchor1().body {
+					Some(ref b) => set_body_index(b, bodies, id),
+					None        => { }
+				}
+
+				match joint.anchor2().body {
+					Some(ref b) => set_body_index(b, bodies, id),
+					None        => { }
+				}
+			}
+
 			// FIXME: avoid allocation
 			let mut joints = Vec::new();
+
 			for (i, c) in constraints.iter().enumerate() {
 				match *c {
-					Constraint::RBRB(ref a, ref b, _) => {
+					Constraint::RBRB(ref a, ref b, _, _) => {
 						set_body_index(a, &mut bodies, &mut id);
 						set_body_index(b, &mut bodies, &mut id);
+						continue;
 					},
-					Constraint::BallInSocket(ref bis) => {
-						joints.push(i);
-						let bbis = bis.read();
-						match bbis.anchor1().body {
-							Some(ref b) => set_body_index(b, &mut bodies, &mut id),
-							None        => { }
-						}
-
-						match bbis.anchor2().body {
-							Some(ref b) => set_body_index(b, &mut bodies, &mut id),
-							None        => { }
-						}
-					},
-					Constraint::Fixed(ref f) => { // FIXME: code duplication from BallInSocket
-						joints.push(i);
-						let bf = f.read();
-						match bf.anchor1().body {
-							Some(ref b) => set_body_index(b, &mut bodies, &mut id),
-							None        => { }
-						}
-
-						match bf.anchor2().body {
-							Some(ref b) => set_body_index(b, &mut bodies, &mut id),
-							None        => { }
-						}
-					}
+					Constraint::BallInSocket(ref bis) => set_joint_body_indices(bis.read().deref(), &mut bodies, &mut id),
+					Constraint::Fixed(ref f)          => set_joint_body_indices(f.read().deref(), &mut bodies, &mut id),
+					Constraint::Revolute(ref r)       => set_joint_body_indices(r.read().deref(), &mut bodies, &mut id),
+					Constraint::Prismatic(ref p)      => set_joint_body_indices(p.read().deref(), &mut bodies, &mut id),
+					Constraint::Spring(ref s)         => set_joint_body_indices(s.read().deref(), &mut bodies, &mut id),
+					Constraint::Rope(ref r)           => set_joint_body_indices(r.read().deref(), &mut bodies, &mut id),
+					Constraint::Custom(ref c)         => set_joint_body_indices(c.read().deref(), &mut bodies, &mut id)
 				}
+
+				joints.push((i, joint_ids[joints.len()]));
 			}
 
-			self.do_solve(dt.clone(), constraints, joints.as_slice(), bodies.as_slice());
+			assert!(joints.len() == joint_ids.len(), "There must be exactly one id per joint.");
+
+			// The impulse cache returns the contacts in the order of their hash.
+			let mut contacts = self.cache.contacts();
+			contacts.sort_by(|&(_, ci1, _), &(_, ci2, _)| ci1.cmp(&ci2));
+
+			self.solve_islands(dt.clone(), constraints, contacts, joints, bodies);
 			self.cache.swap();
+			self.joint_cache.swap();
+		}
+	}
+
+	fn contact_impulses(&self) -> &[ContactImpulse] {
+		self.contact_impulses.as_slice()
+	}
+
+	fn joint_impulses(&self) -> &[JointImpulse] {
+		self.joint_impulses.as_slice()
+	}
+
+	fn warm_start_cache(&self) -> Option<SolverState> {
+		Some(self.cache())
+	}
+
+	fn set_warm_start_cache(&mut self, cache: &SolverState) {
+		self.set_cache(cache)
+	}
+}
+
+/// The bodies, contacts and joints of one island, as indices into the constraints given to the
+/// solver.
+struct IslandBuilder {
+	bodies:   Vec<Arc<RWLock<RigidBody>>>,
+	contacts: Vec<(ContactIdentifier, uint, uint)>,
+	joints:   Vec<(uint, uint)>
+}
+
+impl IslandBuilder {
+	fn new() -> IslandBuilder {
+		IslandBuilder {
+			bodies:   Vec::new(),
+			contacts: Vec::new(),
+			joints:   Vec::new()
+		}
+	}
+}
+
+/// Everything needed to solve one island, possibly on another thread.
+struct Island {
+	bodies:         Vec<Arc<RWLock<RigidBody>>>,
+	// The contacts come first, followed by the joints.
+	constraints:    Vec<Constraint>,
+	// The contact identifier, its index on the constraints given to the solver, and its
+	// warm-starting impulses.
+	contacts:       Vec<(ContactIdentifier, uint, Vec<Scalar>)>,
+	// The joint index on the constraints given to the solver, its id, and the warm-starting
+	// impulses of its first velocity constraints.
+	joints:         Vec<(uint, uint, Vec<Scalar>)>
+}
+
+/// The impulses computed while solving one island.
+struct IslandResult {
+	contact_impulses: Vec<ContactImpulse>,
+	joint_impulses:   Vec<JointImpulse>,
+	contact_cache:    Vec<(ContactIdentifier, Vec<Scalar>)>,
+	joint_cache:      Vec<(uint, Vec<Scalar>)>
+}
+
+impl IslandResult {
+	fn new() -> IslandResult {
+		IslandResult {
+			contact_impulses: Vec::new(),
+			joint_impulses:   Vec::new(),
+			contact_cache:    Vec::new(),
+			joint_cache:      Vec::new()
+		}
+	}
+}
+
+fn merge_result(cache:            &mut ImpulseCache,
+				joint_cache:      &mut JointImpulseCache,
+				contact_impulses: &mut Vec<ContactImpulse>,
+				joint_impulses:   &mut Vec<JointImpulse>,
+				result:           IslandResult) {
+	for (id, imps) in result.contact_cache.into_iter() {
+		let _ = cache.push_impulsions_for(&id).clone_from_slice(imps.as_slice());
+	}
+
+	for (joint, imps) in result.joint_cache.into_iter() {
+		joint_cache.insert(joint, imps.as_slice());
+	}
+
+	contact_impulses.extend(result.contact_impulses.into_iter());
+	joint_impulses.extend(result.joint_impulses.into_iter());
+}
+
+// Solves in parallel if a pool is given and the island is large enough.
+fn solve_velocities(algorithm:      &(ResolutionAlgorithm + Send + Sync),
+					restitution:    &mut [VelocityConstraint],
+					friction:       &mut [VelocityConstraint],
+					mj_lambda:      &mut [Velocities],
+					num_bodies:     uint,
+					num_iterations: uint,
+					is_lambda_zero: bool,
+					pool:           Option<&TaskPool>,
+					num_threads:    uint) {
+	match pool {
+		Some(pool) if restitution.len() + friction.len() >= GRAPH_COLORING_THRESHOLD => {
+			algorithm.solve_parallel(restitution, friction, mj_lambda, num_bodies, num_iterations,
+									 is_lambda_zero, pool, num_threads)
+		},
+		_ => {
+			algorithm.solve(restitution, friction, mj_lambda, num_bodies, num_iterations, is_lambda_zero)
 		}
 	}
 }
 
+fn anchor_index<P>(anchor: &Anchor<P>) -> int {
+	match anchor.body {
+		Some(ref b) => b.read().index(),
+		None        => -1
+	}
+}
+
+// The solver index of the bodies affected by a constraint, -1 if the body cannot move.
+fn constraint_indices(constraint: &Constraint) -> (int, int) {
+	fn joint_indices<T: Joint<M>, M>(joint: &T) -> (int, int) {
+		(anchor_index(joint.anchor1()), anchor_index(joint.anchor2()))
+	}
+
+	match *constraint {
+		Constraint::RBRB(ref a, ref b, _, _) => (a.read().index(), b.read().index()),
+		Constraint::BallInSocket(ref bis)    => joint_indices(bis.read().deref()),
+		Constraint::Fixed(ref f)             => joint_indices(f.read().deref()),
+		Constraint::Revolute(ref r)          => joint_indices(r.read().deref()),
+		Constraint::Prismatic(ref p)         => joint_indices(p.read().deref()),
+		Constraint::Spring(ref s)            => joint_indices(s.read().deref()),
+		Constraint::Rope(ref r)              => joint_indices(r.read().deref()),
+		Constraint::Custom(ref c)            => joint_indices(c.read().deref())
+	}
+}
+
+fn constraint_has_body(constraint: &Constraint) -> bool {
+	let (i1, i2) = constraint_indices(constraint);
+
+	i1 >= 0 || i2 >= 0
+}
+
+// The solver index of one dynamic body affected by a constraint.
+fn constraint_body(constraint: &Constraint) -> uint {
+	let (i1, i2) = constraint_indices(constraint);
+
+	if i1 >= 0 { i1 as uint } else { i2 as uint }
+}
+
+fn union_constraint_bodies(constraint: &Constraint, ufind: &mut [UnionFindSet]) {
+	let (i1, i2) = constraint_indices(constraint);
+
+	if i1 >= 0 && i2 >= 0 {
+		union_find::union(i1 as uint, i2 as uint, ufind)
+	}
+}
+
+fn reset_joint_body_indices<T: Joint<M>, M>(joint: &T) {
+	match joint.anchor1().body {
+		Some(ref b) => b.write().set_index(-2),
+		None        => { }
+	}
+
+	match joint.anchor2().body {
+		Some(ref b) => b.write().set_index(-2),
+		None        => { }
+	}
+}
+
 fn resize_buffer<A: Clone>(buff: &mut Vec<A>, size: uint, val: A) {
 	if buff.len() < size {
 		let diff = size - buff.len();
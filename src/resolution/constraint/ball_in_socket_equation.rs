@@ -1,25 +1,194 @@
+use std::num::{Float, FloatMath};
 use std::sync::RWLockReadGuard;
 use na::{Row, Bounded};
 use na;
-use math::{Scalar, Point, Vect};
+use math::{Scalar, Point, Vect, Orientation, Matrix};
 use object::RigidBody;
-use detection::joint::{Anchor, BallInSocket, Joint};
+use detection::joint::{Anchor, BallInSocket, ConeLimit, TwistLimit, Joint};
 use resolution::constraint::velocity_constraint::VelocityConstraint;
 use resolution::constraint::contact_equation::CorrectionParameters;
 use resolution::constraint::contact_equation;
+use resolution::constraint::joint_equation_helper;
+
+/// The number of velocity constraints generated by a ball-in-socket joint.
+pub fn num_equations(joint: &BallInSocket) -> uint {
+	let mut res = na::dim::<Vect>();
+
+	if joint.cone_limit().is_some() {
+		res = res + 1;
+	}
+
+	// There is no twist in 2D.
+	if joint.twist_limit().is_some() && na::dim::<Orientation>() > 1 {
+		res = res + 2;
+	}
+
+	res + joint.num_motors()
+}
 
 pub fn fill_second_order_equation(dt:          Scalar,
 								  joint:       &BallInSocket,
 								  constraints: &mut [VelocityConstraint],
 								  correction:  &CorrectionParameters) {
 	cancel_relative_linear_motion(
-		dt,
+		dt.clone(),
 		&joint.anchor1_pos(),
 		&joint.anchor2_pos(),
 		joint.anchor1(),
 		joint.anchor2(),
 		constraints,
 		correction);
+
+	let pos1  = joint_equation_helper::body_position(joint.anchor1());
+	let pos2  = joint_equation_helper::body_position(joint.anchor2());
+	let mut i = na::dim::<Vect>();
+
+	match joint.cone_limit() {
+		Some(limit) => {
+			fill_cone_limit(dt.clone(), limit, &pos1, &pos2, joint.anchor1(), joint.anchor2(),
+							&mut constraints[i], correction);
+			i = i + 1;
+		},
+		None => { }
+	}
+
+	if na::dim::<Orientation>() > 1 {
+		match joint.twist_limit() {
+			Some(limit) => {
+				fill_twist_limit(dt.clone(), limit, &pos1, &pos2, joint.anchor1(), joint.anchor2(),
+								 constraints.slice_mut(i, i + 2), correction);
+				i = i + 2;
+			},
+			None => { }
+		}
+	}
+
+	/*
+	 * Motors.
+	 */
+	let mut motor_id = 0u;
+	na::canonical_basis(|local_axis: Orientation| {
+		match joint.motor(motor_id) {
+			Some(motor) => {
+				let constraint = &mut constraints[i];
+				let axis = joint_equation_helper::rotate_orientation(&pos1, &local_axis);
+				let dvel = joint_equation_helper::fill_angular_row(
+					dt.clone(), &axis, joint.anchor1(), joint.anchor2(), constraint);
+
+				joint_equation_helper::set_motor_objective(dt.clone(), dvel, motor, constraint);
+
+				i = i + 1;
+			},
+			None => { }
+		}
+
+		motor_id = motor_id + 1;
+
+		true
+	});
+}
+
+fn fill_cone_limit<P>(dt:         Scalar,
+					  limit:      &ConeLimit,
+					  pos1:       &Matrix,
+					  pos2:       &Matrix,
+					  anchor1:    &Anchor<P>,
+					  anchor2:    &Anchor<P>,
+					  constraint: &mut VelocityConstraint,
+					  correction: &CorrectionParameters) {
+	let axis1 = na::normalize(&na::rotate(pos1, &limit.axis1));
+	let axis2 = na::normalize(&na::rotate(pos2, &limit.axis2));
+
+	let normal: Orientation = na::cross(&axis1, &axis2);
+	let sin_angle = na::norm(&normal);
+	let cos_angle = na::dot(&axis1, &axis2);
+
+	let axis = if sin_angle > Float::epsilon() {
+		-normal / sin_angle
+	}
+	else if cos_angle < na::zero() {
+		// The axes are opposite: the rotation axis is not well defined, but any axis orthogonal
+		// to `axis1` brings them back together.
+		perpendicular_orientation(&axis1)
+	}
+	else {
+		// The axes are aligned: the limit cannot be violated.
+		joint_equation_helper::set_null_row(constraint);
+
+		return;
+	};
+
+	let angle = sin_angle.atan2(cos_angle);
+	let dvel  = joint_equation_helper::fill_angular_row(dt.clone(), &axis, anchor1, anchor2, constraint);
+
+	joint_equation_helper::set_unilateral_objective(dt, dvel, limit.max_angle - angle, constraint, correction);
+}
+
+fn fill_twist_limit<P>(dt:          Scalar,
+					   limit:       &TwistLimit,
+					   pos1:        &Matrix,
+					   pos2:        &Matrix,
+					   anchor1:     &Anchor<P>,
+					   anchor2:     &Anchor<P>,
+					   constraints: &mut [VelocityConstraint],
+					   correction:  &CorrectionParameters) {
+	let axis = na::normalize(&na::rotate(pos1, &limit.axis1));
+	let ref1 = na::rotate(pos1, &limit.ref1);
+	let ref2 = na::rotate(pos2, &limit.ref2);
+
+	// Project the reference vectors on the plane orthogonal to the twist axis.
+	let proj1 = ref1 - axis * na::dot(&ref1, &axis);
+	let proj2 = ref2 - axis * na::dot(&ref2, &axis);
+
+	if na::sqnorm(&proj1) <= Float::epsilon() || na::sqnorm(&proj2) <= Float::epsilon() {
+		// The twist angle is not well defined.
+		joint_equation_helper::set_null_row(&mut constraints[0]);
+		joint_equation_helper::set_null_row(&mut constraints[1]);
+
+		return;
+	}
+
+	let twist_axis = vect_to_orientation(&axis);
+	let angle = na::dot(&na::cross(&proj1, &proj2), &twist_axis).atan2(na::dot(&proj1, &proj2));
+
+	let dvel = joint_equation_helper::fill_angular_row(dt.clone(), &twist_axis, anchor1, anchor2, &mut constraints[0]);
+	joint_equation_helper::set_unilateral_objective(dt.clone(), dvel, angle - limit.min_angle,
+													&mut constraints[0], correction);
+
+	let dvel = joint_equation_helper::fill_angular_row(dt.clone(), &-twist_axis, anchor1, anchor2, &mut constraints[1]);
+	joint_equation_helper::set_unilateral_objective(dt, dvel, limit.max_angle - angle,
+													&mut constraints[1], correction);
+}
+
+// An axis orthogonal to `v`, as a rotation axis.
+#[cfg(feature = "2d")]
+fn perpendicular_orientation(_: &Vect) -> Orientation {
+	// In 2D, every rotation axis is orthogonal to the plane.
+	let mut res: Orientation = na::zero();
+	res[0] = na::one();
+
+	res
+}
+
+// An axis orthogonal to `v`, as a rotation axis.
+#[cfg(not(feature = "2d"))]
+fn perpendicular_orientation(v: &Vect) -> Orientation {
+	let mut res: Vect = na::zero();
+	na::orthonormal_subspace_basis(v, |b: Vect| { res = b; false });
+
+	vect_to_orientation(&res)
+}
+
+// The rotation axis along the vector `v`. There is no such axis in 2D.
+#[cfg(feature = "2d")]
+fn vect_to_orientation(_: &Vect) -> Orientation {
+	panic!("A vector cannot be used as a rotation axis in 2D.")
+}
+
+// The rotation axis along the vector `v`.
+#[cfg(not(feature = "2d"))]
+fn vect_to_orientation(v: &Vect) -> Orientation {
+	v.clone()
 }
 
 // FIXME: move this on another file. Something like "joint_equation_helper.rs"
@@ -79,7 +248,7 @@ pub fn cancel_relative_linear_motion<P>(
 		constraint.lobound   = -_max;
 		constraint.hibound   = _max;
 		constraint.objective = -dvel - error[i] / dt;
-		constraint.impulse   = na::zero(); // FIXME: cache
+		constraint.impulse   = na::zero();
 	}
 }
 
@@ -89,11 +258,13 @@ pub fn write_anchor_id<'a, P>(anchor: &'a Anchor<P>, id: &mut int) -> Option<RWL
 		Some(ref b) => {
 			let rb = b.read();
 			let can_move;
+			let is_kinematic;
 			let rid;
 
 			{
-				can_move = rb.can_move();
-				rid      = rb.index();
+				can_move     = rb.can_move();
+				is_kinematic = rb.is_kinematic();
+				rid          = rb.index();
 			}
 
 			if can_move {
@@ -101,6 +272,12 @@ pub fn write_anchor_id<'a, P>(anchor: &'a Anchor<P>, id: &mut int) -> Option<RWL
 
 				Some(rb)
 			}
+			else if is_kinematic {
+				// The kinematic body velocity matters but it is not affected by the joint.
+				*id = -1;
+
+				Some(rb)
+			}
 			else {
 				*id = -1;
 
@@ -4,10 +4,12 @@ use na;
 use ncollide::geometry::Contact;
 use ncollide::volumetric::InertiaTensor;
 use resolution::constraint::velocity_constraint::VelocityConstraint;
+use detection::constraint::ContactProperties;
 use object::RigidBody;
 use math::{Scalar, Point, Vect, Orientation};
 
 /// The correction coefficient used by the constraint solver.
+#[deriving(Clone)]
 pub enum CorrectionMode {
     /// Penetration are solved by the penalty method.
     Velocity(Scalar),
@@ -61,10 +63,18 @@ impl CorrectionMode {
     }
 }
 
+/// The parameters used by the constraint solver to correct the positional errors.
+#[deriving(Clone)]
 pub struct CorrectionParameters {
+    /// The correction mode used for contacts.
     pub corr_mode:       CorrectionMode,
+    /// The fraction of the positional error of joints corrected at each step.
     pub joint_corr:      Scalar,
-    pub rest_eps:        Scalar
+    /// The relative velocity under which restitution is ignored.
+    pub rest_eps:        Scalar,
+    /// The relative tangential velocity under which the surfaces do not slide and static friction
+    /// is used.
+    pub slip_eps:        Scalar
 }
 
 pub fn reinit_to_first_order_equation(dt:         Scalar,
@@ -89,6 +99,7 @@ pub fn reinit_to_first_order_equation(dt:         Scalar,
 
 pub fn fill_second_order_equation(dt:           Scalar,
                                   coll:         &Contact<Scalar, Point, Vect>,
+                                  props:        &ContactProperties,
                                   rb1:          &RigidBody,
                                   rb2:          &RigidBody,
                                   rconstraint:  &mut VelocityConstraint,
@@ -97,7 +108,7 @@ pub fn fill_second_order_equation(dt:           Scalar,
                                   idf:          uint,
                                   cache:        &[Scalar],
                                   correction:   &CorrectionParameters) {
-    let restitution = rb1.restitution() * rb2.restitution();
+    let restitution = props.restitution.clone();
 
     let center = na::center(&coll.world1, &coll.world2);
 
@@ -115,18 +126,17 @@ pub fn fill_second_order_equation(dt:           Scalar,
                              correction);
 
 
-    let friction  = rb1.friction() * rb2.friction();
     // To bound the friction we use the last frame normal impulse.
     // That means we have to make a special case for the first time the contact appears.
     // In that case, we estimate the impulse by the derired normal correction.
 
     let mut i = 0;
 
-    na::orthonormal_subspace_basis(&coll.normal, |friction_axis| {
+    na::orthonormal_subspace_basis(&coll.normal, |friction_axis: Vect| {
         let constraint = &mut fconstraints[idf + i];
 
         fill_velocity_constraint(dt.clone(),
-                                 friction_axis,
+                                 friction_axis.clone(),
                                  center.clone(),
                                  na::zero(),
                                  na::zero(),
@@ -138,12 +148,28 @@ pub fn fill_second_order_equation(dt:           Scalar,
                                  constraint,
                                  correction);
 
-        constraint.friction_coeff    = friction.clone();
+        // Drive the relative tangential velocity toward the surface velocity.
+        constraint.objective         = constraint.objective + na::dot(&props.surface_velocity, &friction_axis);
         constraint.friction_limit_id = idr;
         i = i + 1;
 
         true
-    })
+    });
+
+    // Use the static friction coefficient if the surfaces do not slide.
+    let fconstraints = fconstraints.slice_mut(idf, idf + i);
+    let sqslip       = fconstraints.iter().fold(na::zero(), |sum: Scalar, c| sum + c.objective * c.objective);
+    let friction     =
+        if sqslip <= correction.slip_eps * correction.slip_eps {
+            props.static_friction.clone()
+        }
+        else {
+            props.friction.clone()
+        };
+
+    for constraint in fconstraints.iter_mut() {
+        constraint.friction_coeff = friction.clone();
+    }
 }
 
 pub fn fill_constraint_geometry(normal:     Vect,
@@ -154,6 +180,7 @@ pub fn fill_constraint_geometry(normal:     Vect,
                                 constraint: &mut VelocityConstraint) {
     constraint.normal             = normal;
     constraint.inv_projected_mass = na::zero();
+    constraint.cfm                = na::zero();
 
     match *rb1 {
         Some(ref rb) => {
@@ -204,8 +231,10 @@ fn fill_velocity_constraint(dt:              Scalar,
     let rot_axis1 = na::cross(&(center - *rb1.center_of_mass()), &-normal);
     let rot_axis2 = na::cross(&(center - *rb2.center_of_mass()), &normal);
 
-    let opt_rb1 = if rb1.can_move() { Some(rb1) } else { None };
-    let opt_rb2 = if rb2.can_move() { Some(rb2) } else { None };
+    // Kinematic bodies have a zero inverse mass: their velocity is taken in account but they are
+    // not affected by the constraint.
+    let opt_rb1 = if rb1.can_move() || rb1.is_kinematic() { Some(rb1) } else { None };
+    let opt_rb2 = if rb2.can_move() || rb2.is_kinematic() { Some(rb2) } else { None };
     fill_constraint_geometry(normal, rot_axis1, rot_axis2, &opt_rb1, &opt_rb2, constraint);
 
     /*
@@ -68,7 +68,7 @@ pub fn cancel_relative_angular_motion<P>(dt:          Scalar,
         // FIXME: dont compute the difference at each iteration
         let error = na::dot(&delta_rot, &rot_axis) * correction.joint_corr / dt;
         constraint.objective = na::dot(&(ang_vel2 - ang_vel1), &rot_axis) - error;
-        constraint.impulse   = na::zero(); // FIXME: cache
+        constraint.impulse   = na::zero();
 
         i = i + 1;
 
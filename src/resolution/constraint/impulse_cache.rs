@@ -7,12 +7,14 @@ use std::hash::sip::{SipHasher, SipState};
 use rand::{IsaacRng, Rng};
 use std::collections::HashMap;
 use na;
-use na::IterableMut;
+use na::{Iterable, IterableMut};
 use math::{Scalar, Point};
 use ncollide::utils::AsBytes;
 
-#[deriving(PartialEq)]
+#[deriving(PartialEq, Clone)]
 /// The identifier of a contact stored in the impulse cache.
+///
+/// The two objects are identified by the stable id of the rigid bodies in contact.
 pub struct ContactIdentifier {
     obj1:    uint,
     obj2:    uint,
@@ -44,6 +46,7 @@ impl ContactIdentifier {
     }
 }
 
+#[deriving(Clone)]
 pub struct ImpulseCache {
     hash_prev:           HashMap<ContactIdentifier, (uint, uint), SipHasher>,
     cache_prev:          Vec<Scalar>,
@@ -98,6 +101,41 @@ impl ImpulseCache {
         self.cache_next.slice_mut(begin, end)
     }
 
+    /// The contacts inserted since the last swap, with their constraint index and the offset of
+    /// their previous impulses.
+    pub fn contacts(&self) -> Vec<(ContactIdentifier, uint, uint)> {
+        self.hash_next.iter().map(|(id, &(cid, imp))| (id.clone(), cid, imp)).collect()
+    }
+
+    /// Allocates the impulses of the contact `id` for the next step.
+    pub fn push_impulsions_for(&mut self, id: &ContactIdentifier) -> &mut [Scalar] {
+        let begin = self.cache_next.len();
+
+        for _ in range(0, self.impulse_per_contact) {
+            self.cache_next.push(na::zero());
+        }
+
+        match self.hash_next.get_mut(id) {
+            Some(kv) => *kv = (kv.val0(), begin),
+            None     => { }
+        }
+
+        let end = self.cache_next.len();
+
+        self.cache_next.slice_mut(begin, end)
+    }
+
+    /// Keeps the impulses of the contact `id` unchanged for the next step.
+    pub fn keep_impulsions(&mut self, id: &ContactIdentifier) {
+        match self.hash_next.get(id).cloned() {
+            Some((_, imp)) => {
+                let imps = self.cache_prev.slice(imp, imp + self.impulse_per_contact).to_vec();
+                let _ = self.push_impulsions_for(id).clone_from_slice(imps.as_slice());
+            },
+            None => { }
+        }
+    }
+
     pub fn reserved_impulse_offset(&self) -> uint {
         self.impulse_per_contact
     }
@@ -126,4 +164,53 @@ impl ImpulseCache {
         self.hash_next.clear();
         self.cache_next.truncate(self.impulse_per_contact);
     }
+
+    /// Appends the impulses kept since the last swap to `integers` and `reals`.
+    pub fn export(&self, integers: &mut Vec<uint>, reals: &mut Vec<Scalar>) {
+        let mut entries: Vec<(&ContactIdentifier, &(uint, uint))> = self.hash_prev.iter().collect();
+
+        entries.sort_by(|&(_, &(cid1, _)), &(_, &(cid2, _))| cid1.cmp(&cid2));
+        integers.push(entries.len());
+
+        for &(id, &(cid, imp)) in entries.iter() {
+            integers.push(id.obj1);
+            integers.push(id.obj2);
+            integers.push(cid);
+            reals.extend(id.ccenter.iter().map(|x| *x));
+            reals.push_all(self.cache_prev.slice(imp, imp + self.impulse_per_contact));
+        }
+    }
+
+    /// Replaces the impulses kept since the last swap by the ones written by `export`.
+    pub fn import<I: Iterator<uint>, R: Iterator<Scalar>>(&mut self, integers: &mut I, reals: &mut R) {
+        self.clear();
+
+        let len = integers.next().expect("Invalid impulse cache state.");
+
+        for _ in range(0, len) {
+            let obj1 = integers.next().expect("Invalid impulse cache state.");
+            let obj2 = integers.next().expect("Invalid impulse cache state.");
+            let cid  = integers.next().expect("Invalid impulse cache state.");
+
+            let mut ccenter: Point = na::orig();
+
+            for x in ccenter.iter_mut() {
+                *x = reals.next().expect("Invalid impulse cache state.");
+            }
+
+            let imp = self.cache_prev.len();
+
+            for _ in range(0, self.impulse_per_contact) {
+                self.cache_prev.push(reals.next().expect("Invalid impulse cache state."));
+            }
+
+            let id = ContactIdentifier {
+                obj1:    obj1,
+                obj2:    obj2,
+                ccenter: ccenter
+            };
+
+            let _ = self.hash_prev.insert(id, (cid, imp));
+        }
+    }
 }
@@ -0,0 +1,191 @@
+//! Helper functions to fill the velocity constraints generated by joints.
+
+use na::{Rotation, Bounded};
+use na;
+use detection::joint::{Anchor, Motor};
+use resolution::constraint::velocity_constraint::VelocityConstraint;
+use resolution::constraint::ball_in_socket_equation;
+use resolution::constraint::contact_equation;
+use resolution::constraint::contact_equation::CorrectionParameters;
+use math::{Scalar, Point, Vect, Orientation, Matrix};
+
+/// The position of the body attached to `anchor`, or the identity if it is attached to the ground.
+pub fn body_position<P>(anchor: &Anchor<P>) -> Matrix {
+    match anchor.body {
+        Some(ref b) => b.read().position().clone(),
+        None        => na::one()
+    }
+}
+
+/// Expresses, in global coordinates, the rotation axis `axis` given in the local frame `m`.
+pub fn rotate_orientation(m: &Matrix, axis: &Orientation) -> Orientation {
+    // NOTE: we conjugate the rotation instead of rotating the axis directly since `Orientation`
+    // is not a vector of the space in 2D.
+    let mut rot: Matrix = na::one();
+    rot.append_rotation(axis);
+
+    (*m * rot * na::inv(m).expect("m must be inversible.")).rotation()
+}
+
+/// Fills the geometry of a constraint acting on the relative linear velocity along `axis` of the
+/// attach points `global1` and `global2`.
+///
+/// The constraint is oriented such that a positive impulse increases the velocity of `global2`
+/// relative to `global1`. Returns the current value of this relative velocity.
+pub fn fill_linear_row<P>(dt:         Scalar,
+                          axis:       &Vect,
+                          global1:    &Point,
+                          global2:    &Point,
+                          anchor1:    &Anchor<P>,
+                          anchor2:    &Anchor<P>,
+                          constraint: &mut VelocityConstraint)
+                          -> Scalar {
+    let rot_axis1 = na::cross(&(*global1 - anchor1.center_of_mass()), &-*axis);
+    let rot_axis2 = na::cross(&(*global2 - anchor2.center_of_mass()), axis);
+
+    let opt_rb1 = ball_in_socket_equation::write_anchor_id(anchor1, &mut constraint.id1);
+    let opt_rb2 = ball_in_socket_equation::write_anchor_id(anchor2, &mut constraint.id2);
+
+    let dvel = contact_equation::relative_velocity(
+        &opt_rb1.as_ref().map(|r| &**r),
+        &opt_rb2.as_ref().map(|r| &**r),
+        axis,
+        &rot_axis1,
+        &rot_axis2,
+        &dt);
+
+    contact_equation::fill_constraint_geometry(
+        axis.clone(),
+        rot_axis1,
+        rot_axis2,
+        &opt_rb1.as_ref().map(|r| &**r),
+        &opt_rb2.as_ref().map(|r| &**r),
+        constraint
+    );
+
+    dvel
+}
+
+/// Fills the geometry of a constraint acting on the relative angular velocity along `axis`.
+///
+/// The constraint is oriented such that a positive impulse increases the angular velocity of the
+/// second body relative to the first one. Returns the current value of this relative velocity.
+pub fn fill_angular_row<P>(dt:         Scalar,
+                           axis:       &Orientation,
+                           anchor1:    &Anchor<P>,
+                           anchor2:    &Anchor<P>,
+                           constraint: &mut VelocityConstraint)
+                           -> Scalar {
+    let opt_rb1 = ball_in_socket_equation::write_anchor_id(anchor1, &mut constraint.id1);
+    let opt_rb2 = ball_in_socket_equation::write_anchor_id(anchor2, &mut constraint.id2);
+
+    let rot_axis1 = -*axis;
+    let rot_axis2 = axis.clone();
+
+    let dvel = contact_equation::relative_velocity(
+        &opt_rb1.as_ref().map(|r| &**r),
+        &opt_rb2.as_ref().map(|r| &**r),
+        &na::zero(),
+        &rot_axis1,
+        &rot_axis2,
+        &dt);
+
+    contact_equation::fill_constraint_geometry(
+        na::zero(),
+        rot_axis1,
+        rot_axis2,
+        &opt_rb1.as_ref().map(|r| &**r),
+        &opt_rb2.as_ref().map(|r| &**r),
+        constraint
+    );
+
+    dvel
+}
+
+/// Sets up a constraint that cancels its relative velocity `dvel` and the positional error `error`.
+pub fn set_bilateral_objective(dt:         Scalar,
+                               dvel:       Scalar,
+                               error:      Scalar,
+                               constraint: &mut VelocityConstraint) {
+    let _max: Scalar = Bounded::max_value();
+    constraint.lobound   = -_max;
+    constraint.hibound   = _max;
+    constraint.objective = -dvel - error / dt;
+    constraint.impulse   = na::zero();
+}
+
+/// Sets up a one-sided constraint that keeps the positional quantity `value` non-negative.
+///
+/// `dvel` is the current rate of change of `value`. If `value` is positive, the constraint lets it
+/// decrease down to zero during the next time step.
+pub fn set_unilateral_objective(dt:         Scalar,
+                                dvel:       Scalar,
+                                value:      Scalar,
+                                constraint: &mut VelocityConstraint,
+                                correction: &CorrectionParameters) {
+    let target = if value < na::zero() { -value * correction.joint_corr / dt } else { -value / dt };
+
+    constraint.lobound   = na::zero();
+    constraint.hibound   = Bounded::max_value();
+    constraint.objective = target - dvel;
+    constraint.impulse   = na::zero();
+}
+
+/// Sets up a soft constraint that behaves like a damped spring acting on the positional `error`.
+///
+/// The constraint is solved implicitly using constraint force mixing so that it remains stable for
+/// high stiffnesses.
+pub fn set_soft_objective(dt:         Scalar,
+                          dvel:       Scalar,
+                          error:      Scalar,
+                          stiffness:  Scalar,
+                          damping:    Scalar,
+                          constraint: &mut VelocityConstraint) {
+    let denom = damping + dt * stiffness;
+
+    if denom <= na::zero() {
+        // Neither stiff nor damped: the spring has no effect.
+        set_null_row(constraint);
+    }
+    else {
+        let _1: Scalar = na::one();
+        let _max: Scalar = Bounded::max_value();
+
+        constraint.cfm                = _1 / (dt * denom);
+        constraint.inv_projected_mass = _1 / (_1 / constraint.inv_projected_mass + constraint.cfm);
+        constraint.lobound            = -_max;
+        constraint.hibound            = _max;
+        constraint.objective          = -dvel - error * stiffness / denom;
+        constraint.impulse            = na::zero();
+    }
+}
+
+/// Sets up a constraint that drives its relative velocity `dvel` toward the target velocity of
+/// `motor`.
+///
+/// The impulse applied by the motor during one time step is bounded by its maximum force.
+pub fn set_motor_objective(dt:         Scalar,
+                           dvel:       Scalar,
+                           motor:      &Motor,
+                           constraint: &mut VelocityConstraint) {
+    let max_impulse = motor.max_force * dt;
+
+    constraint.lobound   = -max_impulse;
+    constraint.hibound   = max_impulse;
+    constraint.objective = motor.target_vel - dvel;
+    constraint.impulse   = na::zero();
+}
+
+/// Sets up a constraint that has no effect.
+///
+/// This is used when a constraint is not well defined for the current configuration.
+pub fn set_null_row(constraint: &mut VelocityConstraint) {
+    constraint.id1                = -1;
+    constraint.id2                = -1;
+    constraint.inv_projected_mass = na::zero();
+    constraint.cfm                = na::zero();
+    constraint.lobound            = na::zero();
+    constraint.hibound            = na::zero();
+    constraint.objective          = na::zero();
+    constraint.impulse            = na::zero();
+}
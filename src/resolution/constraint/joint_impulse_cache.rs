@@ -0,0 +1,91 @@
+#![doc(hidden)]
+
+use std::mem;
+use std::collections::HashMap;
+use math::Scalar;
+
+/// Impulses applied by each velocity constraint of each joint during the last time step.
+///
+/// The impulses are identified by the id of their joint, as given by `JointManager::joint_id`,
+/// and the index of their velocity constraint inside the joint. Those do not depend on the order
+/// the joints are given to the solver, nor on the joint addresses.
+#[deriving(Clone)]
+pub struct JointImpulseCache {
+    hash_prev: HashMap<(uint, uint), Scalar>,
+    hash_next: HashMap<(uint, uint), Scalar>
+}
+
+impl JointImpulseCache {
+    pub fn new() -> JointImpulseCache {
+        JointImpulseCache {
+            hash_prev: HashMap::new(),
+            hash_next: HashMap::new()
+        }
+    }
+
+    /// The impulses applied by the first velocity constraints of `joint`.
+    pub fn impulsions(&self, joint: uint) -> Vec<Scalar> {
+        let mut res = Vec::new();
+
+        loop {
+            match self.hash_prev.get(&(joint, res.len())) {
+                Some(imp) => res.push(imp.clone()),
+                None      => return res
+            }
+        }
+    }
+
+    pub fn insert(&mut self, joint: uint, impulsions: &[Scalar]) {
+        for (row, imp) in impulsions.iter().enumerate() {
+            let _ = self.hash_next.insert((joint, row), imp.clone());
+        }
+    }
+
+    /// Keeps the impulses of `joint` unchanged for the next step.
+    pub fn keep(&mut self, joint: uint) {
+        let imps = self.impulsions(joint);
+
+        self.insert(joint, imps.as_slice())
+    }
+
+    pub fn clear(&mut self) {
+        self.hash_prev.clear();
+        self.hash_next.clear();
+    }
+
+    pub fn swap(&mut self) {
+        mem::swap(&mut self.hash_prev, &mut self.hash_next);
+        self.hash_next.clear();
+    }
+
+    /// Appends the impulses kept since the last swap to `integers` and `reals`.
+    pub fn export(&self, integers: &mut Vec<uint>, reals: &mut Vec<Scalar>) {
+        let mut entries: Vec<(&(uint, uint), &Scalar)> = self.hash_prev.iter().collect();
+
+        entries.sort_by(|&(k1, _), &(k2, _)| k1.cmp(k2));
+        integers.push(entries.len());
+
+        for &(&(joint, row), imp) in entries.iter() {
+            integers.push(joint);
+            integers.push(row);
+            reals.push(imp.clone());
+        }
+    }
+
+    /// Replaces the impulses kept since the last swap by the ones written by `export`.
+    pub fn import<I: Iterator<uint>, R: Iterator<Scalar>>(&mut self, integers: &mut I, reals: &mut R) {
+        self.clear();
+
+        let len = integers.next().expect("Invalid joint impulse cache state.");
+
+        for _ in range(0, len) {
+            let joint = integers.next().expect("Invalid joint impulse cache state.");
+            let row   = integers.next().expect("Invalid joint impulse cache state.");
+            let imp   = reals.next().expect("Invalid joint impulse cache state.");
+
+            let _ = self.hash_next.insert((joint, row), imp);
+        }
+
+        self.swap();
+    }
+}
@@ -0,0 +1,98 @@
+use na;
+use math::Scalar;
+use resolution::constraint::velocity_constraint::VelocityConstraint;
+use resolution::constraint::projected_gauss_seidel_solver::Velocities;
+use resolution::constraint::projected_gauss_seidel_solver as pgs;
+
+/// Solve a set of velocity constraints using the nonsmooth nonlinear conjugate gradient solver.
+///
+/// Each iteration performs a projected gauss seidel sweep, and then moves the impulses further
+/// along a search direction built from the variations of impulses of the previous sweeps (see
+/// Silcowitz, Niebe and Erleben, "A nonsmooth nonlinear conjugate gradient method for interactive
+/// contact force problems"). The search direction is reset whenever the residual grows, and the
+/// impulses moved along it are projected back onto their bounds. This usually converges much
+/// faster than the projected gauss seidel solver on large stacks.
+///
+/// The arguments are the same as for `projected_gauss_seidel_solve`.
+pub fn nonsmooth_nonlinear_conjugate_gradient_solve(restitution:    &mut [VelocityConstraint],
+                                                    friction:       &mut [VelocityConstraint],
+                                                    result:         &mut [Velocities],
+                                                    num_bodies:     uint,
+                                                    num_iterations: uint,
+                                                    is_lambda_zero: bool) {
+    assert!(result.len() == num_bodies);
+
+    pgs::init_velocities(restitution, friction, result, is_lambda_zero);
+
+    let num_constraints   = restitution.len() + friction.len();
+    let mut impulses      = Vec::from_elem(num_constraints, na::zero::<Scalar>());
+    let mut direction     = Vec::from_elem(num_constraints, na::zero::<Scalar>());
+    let mut prev_residual = na::zero::<Scalar>();
+
+    /*
+     * solve the system
+     */
+    for k in range(0, num_iterations) {
+        for (imp, c) in impulses.iter_mut().zip(restitution.iter().chain(friction.iter())) {
+            *imp = c.impulse.clone();
+        }
+
+        pgs::projected_gauss_seidel_iteration(restitution, friction, result, na::one());
+
+        // The residual is the squared norm of the impulse variations due to the sweep.
+        let mut residual = na::zero::<Scalar>();
+
+        for (imp, c) in impulses.iter().zip(restitution.iter().chain(friction.iter())) {
+            let g = c.impulse - *imp;
+            residual = residual + g * g;
+        }
+
+        let beta = if k == 0 || prev_residual == na::zero() { na::zero() } else { residual / prev_residual };
+
+        if beta > na::one() {
+            // Restart from the steepest descent.
+            for p in direction.iter_mut() {
+                *p = na::zero();
+            }
+        }
+        else {
+            let (rdirection, fdirection) = direction.as_mut_slice().split_at_mut(restitution.len());
+            let (rimpulses, fimpulses)   = impulses.as_slice().split_at(restitution.len());
+
+            for ((c, imp), p) in restitution.iter_mut().zip(rimpulses.iter()).zip(rdirection.iter_mut()) {
+                conjugate_step(c, *imp, p, beta, result);
+            }
+
+            // The friction bounds depend on the restitution impulses moved by the step above.
+            for ((c, imp), p) in friction.iter_mut().zip(fimpulses.iter()).zip(fdirection.iter_mut()) {
+                if pgs::update_friction_bounds(c, restitution) {
+                    conjugate_step(c, *imp, p, beta, result);
+                }
+                else {
+                    *p = c.impulse - *imp;
+                }
+            }
+        }
+
+        prev_residual = residual;
+    }
+}
+
+// Moves the impulse of one constraint along the search direction `p`, and updates `p`.
+//
+// Like the sweep, the step is projected such that the impulse stays within its bounds: the search
+// direction alone may push a resting contact into pulling its bodies together.
+fn conjugate_step(c:         &mut VelocityConstraint,
+                  prev:      Scalar,
+                  p:         &mut Scalar,
+                  beta:      Scalar,
+                  mj_lambda: &mut [Velocities]) {
+    let g       = c.impulse - prev;
+    let impulse = na::clamp(c.impulse + beta * *p, c.lobound, c.hibound);
+    let extra   = impulse - c.impulse;
+
+    c.impulse = impulse;
+    pgs::apply_impulse(c, extra, mj_lambda);
+
+    *p = extra + g;
+}
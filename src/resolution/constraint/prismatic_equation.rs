@@ -0,0 +1,118 @@
+use na::Translate;
+use na;
+use detection::joint::{Prismatic, Joint};
+use resolution::constraint::fixed_equation;
+use resolution::constraint::joint_equation_helper;
+use resolution::constraint::velocity_constraint::VelocityConstraint;
+use resolution::constraint::contact_equation::CorrectionParameters;
+use math::{Scalar, Point, Vect, Orientation};
+
+/// The number of velocity constraints generated by a prismatic joint.
+pub fn num_equations(joint: &Prismatic) -> uint {
+    let mut res = na::dim::<Vect>() - 1 + na::dim::<Orientation>();
+
+    if joint.min_translation().is_some() {
+        res = res + 1;
+    }
+
+    if joint.max_translation().is_some() {
+        res = res + 1;
+    }
+
+    if joint.motor().is_some() {
+        res = res + 1;
+    }
+
+    res
+}
+
+pub fn fill_second_order_equation(dt:          Scalar,
+                                  joint:       &Prismatic,
+                                  constraints: &mut [VelocityConstraint],
+                                  correction:  &CorrectionParameters) {
+    let ref1 = joint.anchor1_pos();
+    let ref2 = joint.anchor2_pos();
+
+    let global1: Point = ref1.translate(&na::orig());
+    let global2: Point = ref2.translate(&na::orig());
+    let axis           = joint.world_axis();
+
+    // Cancel the relative translations along every axis orthogonal to the joint axis.
+    let mut i = 0;
+    na::orthonormal_subspace_basis(&axis, |lin_axis: Vect| {
+        let constraint = &mut constraints[i];
+
+        let dvel  = joint_equation_helper::fill_linear_row(
+            dt.clone(),
+            &lin_axis,
+            &global1,
+            &global2,
+            joint.anchor1(),
+            joint.anchor2(),
+            constraint);
+        let error = na::dot(&(global2 - global1), &lin_axis) * correction.joint_corr;
+
+        joint_equation_helper::set_bilateral_objective(dt.clone(), dvel, error, constraint);
+
+        i = i + 1;
+
+        true
+    });
+
+    fixed_equation::cancel_relative_angular_motion(
+        dt.clone(),
+        &ref1,
+        &ref2,
+        joint.anchor1(),
+        joint.anchor2(),
+        constraints.slice_from_mut(na::dim::<Vect>() - 1),
+        correction);
+
+    /*
+     * Linear limits.
+     */
+    let mut i       = na::dim::<Vect>() - 1 + na::dim::<Orientation>();
+    let translation = na::dot(&(global2 - global1), &axis);
+
+    match joint.min_translation() {
+        Some(min_translation) => {
+            let constraint = &mut constraints[i];
+            let dvel = joint_equation_helper::fill_linear_row(
+                dt.clone(), &axis, &global1, &global2, joint.anchor1(), joint.anchor2(), constraint);
+
+            joint_equation_helper::set_unilateral_objective(
+                dt.clone(), dvel, translation - min_translation, constraint, correction);
+
+            i = i + 1;
+        },
+        None => { }
+    }
+
+    match joint.max_translation() {
+        Some(max_translation) => {
+            let constraint = &mut constraints[i];
+            let dvel = joint_equation_helper::fill_linear_row(
+                dt.clone(), &-axis, &global1, &global2, joint.anchor1(), joint.anchor2(), constraint);
+
+            joint_equation_helper::set_unilateral_objective(
+                dt.clone(), dvel, max_translation - translation, constraint, correction);
+
+            i = i + 1;
+        },
+        None => { }
+    }
+
+    /*
+     * Motor.
+     */
+    match joint.motor() {
+        Some(motor) => {
+            let constraint = &mut constraints[i];
+            let dvel = joint_equation_helper::fill_linear_row(
+                dt.clone(), &axis, &global1, &global2, joint.anchor1(), joint.anchor2(), constraint);
+
+            joint_equation_helper::set_motor_objective(dt, dvel, motor, constraint);
+        },
+        None => { }
+    }
+}
@@ -1,5 +1,8 @@
+use std::cmp;
+use std::sync::{Arc, TaskPool};
+use std::comm::channel;
 use na;
-use math::{Vect, Orientation};
+use math::{Scalar, Vect, Orientation};
 use resolution::constraint::velocity_constraint::VelocityConstraint;
 
 /// Structure holding the result of the projected gauss seidel solver.
@@ -45,65 +48,344 @@ pub fn projected_gauss_seidel_solve(restitution:    &mut [VelocityConstraint],
                                     num_bodies:     uint,
                                     num_iterations: uint,
                                     is_lambda_zero: bool) {
-    // initialize the solution with zeros...
-    // mj_lambda is result
+    successive_over_relaxation_solve(restitution, friction, result, num_bodies, num_iterations,
+                                     is_lambda_zero, na::one())
+}
+
+/// Solve a set of velocity constraints using the successive over-relaxation solver.
+///
+/// This is the projected gauss seidel solver where each impulse update is multiplied by
+/// `relaxation` before being clamped. A factor greater than one (usually smaller than two) may
+/// speed up the convergence, while a factor equal to one gives exactly the projected gauss seidel
+/// solver. The other arguments are the same as for `projected_gauss_seidel_solve`.
+pub fn successive_over_relaxation_solve(restitution:    &mut [VelocityConstraint],
+                                        friction:       &mut [VelocityConstraint],
+                                        result:         &mut [Velocities],
+                                        num_bodies:     uint,
+                                        num_iterations: uint,
+                                        is_lambda_zero: bool,
+                                        relaxation:     Scalar) {
     assert!(result.len() == num_bodies);
 
-    for v in result.iter_mut() {
+    init_velocities(restitution, friction, result, is_lambda_zero);
+
+    /*
+     * solve the system
+     */
+    for _ in range(0, num_iterations) {
+        projected_gauss_seidel_iteration(restitution, friction, result, relaxation.clone());
+    }
+}
+
+/// Initializes the velocities with zeros, and warm starts them if `is_lambda_zero` is `false`.
+pub fn init_velocities(restitution:    &[VelocityConstraint],
+                       friction:       &[VelocityConstraint],
+                       mj_lambda:      &mut [Velocities],
+                       is_lambda_zero: bool) {
+    // initialize the solution with zeros...
+    for v in mj_lambda.iter_mut() {
         v.reset();
     }
 
     // ... and warm start if possible
     if !is_lambda_zero {
         for c in restitution.iter() {
-            setup_warmstart_for_constraint(c, result);
+            setup_warmstart_for_constraint(c, mj_lambda);
         }
 
         for c in friction.iter() {
-            setup_warmstart_for_constraint(c, result);
+            setup_warmstart_for_constraint(c, mj_lambda);
+        }
+    }
+}
+
+/// Performs one projected gauss seidel sweep over every constraint.
+pub fn projected_gauss_seidel_iteration(restitution: &mut [VelocityConstraint],
+                                        friction:    &mut [VelocityConstraint],
+                                        mj_lambda:   &mut [Velocities],
+                                        relaxation:  Scalar) {
+    for c in restitution.iter_mut() {
+        solve_velocity_constraint(c, mj_lambda, relaxation.clone());
+    }
+
+    for c in friction.iter_mut() {
+        if update_friction_bounds(c, restitution) {
+            solve_velocity_constraint(c, mj_lambda, relaxation.clone());
         }
     }
+}
+
+/// Solve a set of velocity constraints using the projected gauss seidel solver, parallelized by
+/// graph coloring.
+///
+/// The constraints are split into batches such that no two constraints of the same batch affect
+/// the same body. The constraints of each batch are then solved concurrently by `num_threads`
+/// jobs executed on `pool`. Because the constraints are not visited in the same order, the result
+/// is slightly different from the one of `projected_gauss_seidel_solve`, but it converges as
+/// fast.
+///
+/// The other arguments are the same as for `projected_gauss_seidel_solve`.
+pub fn colored_projected_gauss_seidel_solve(restitution:    &mut [VelocityConstraint],
+                                            friction:       &mut [VelocityConstraint],
+                                            result:         &mut [Velocities],
+                                            num_bodies:     uint,
+                                            num_iterations: uint,
+                                            is_lambda_zero: bool,
+                                            pool:           &TaskPool,
+                                            num_threads:    uint) {
+    assert!(result.len() == num_bodies);
+    assert!(num_threads > 0);
+
+    init_velocities(restitution, friction, result, is_lambda_zero);
+
+    let restitution_batches = color_constraints(restitution, num_bodies);
+    let friction_batches    = color_constraints(friction, num_bodies);
+
+    let buffers = SharedBuffers {
+        restitution:     restitution.as_mut_ptr() as uint,
+        num_restitution: restitution.len(),
+        friction:        friction.as_mut_ptr() as uint,
+        num_friction:    friction.len(),
+        mj_lambda:       result.as_mut_ptr() as uint,
+        num_bodies:      num_bodies
+    };
 
     /*
      * solve the system
      */
     for _ in range(0, num_iterations) {
-        for c in restitution.iter_mut() {
-            solve_velocity_constraint(c, result);
+        // The friction constraints read the restitution impulses: they must be solved
+        // separately.
+        for batch in restitution_batches.iter() {
+            solve_batch(pool, num_threads, batch, &buffers, false);
         }
 
-        for c in friction.iter_mut() {
-            let impulse = restitution[c.friction_limit_id].impulse.clone();
+        for batch in friction_batches.iter() {
+            solve_batch(pool, num_threads, batch, &buffers, true);
+        }
+    }
+}
+
+// The constraints and velocities buffers, shared between the jobs solving one batch.
+//
+// They are passed as addresses because jobs must be `'static`. The jobs only access the elements
+// they own through raw pointers: the constraints of their chunk, and the velocities of the bodies
+// affected by those constraints, which are not shared with any other constraint of the batch.
+// The restitution constraints are only read while the friction constraints are solved.
+// `solve_batch` waits for every job to complete, or to fail, before returning.
+#[deriving(Clone)]
+struct SharedBuffers {
+    restitution:     uint,
+    num_restitution: uint,
+    friction:        uint,
+    num_friction:    uint,
+    mj_lambda:       uint,
+    num_bodies:      uint
+}
+
+// Splits the constraints into batches that do not share any body.
+fn color_constraints(constraints: &[VelocityConstraint], num_bodies: uint) -> Vec<Arc<Vec<uint>>> {
+    let mut batches: Vec<Vec<uint>> = Vec::new();
+    let mut used:    Vec<Vec<bool>> = Vec::new();
+
+    for (i, c) in constraints.iter().enumerate() {
+        let mut color = 0;
+
+        loop {
+            if color == batches.len() {
+                batches.push(Vec::new());
+                used.push(Vec::from_elem(num_bodies, false));
+            }
+
+            let conflict1 = c.id1 >= 0 && used[color][c.id1 as uint];
+            let conflict2 = c.id2 >= 0 && used[color][c.id2 as uint];
+
+            if !conflict1 && !conflict2 {
+                if c.id1 >= 0 {
+                    used[color][c.id1 as uint] = true;
+                }
+
+                if c.id2 >= 0 {
+                    used[color][c.id2 as uint] = true;
+                }
+
+                batches[color].push(i);
+
+                break;
+            }
+
+            color = color + 1;
+        }
+    }
+
+    batches.into_iter().map(|b| Arc::new(b)).collect()
+}
+
+fn solve_batch(pool:        &TaskPool,
+               num_threads: uint,
+               batch:       &Arc<Vec<uint>>,
+               buffers:     &SharedBuffers,
+               is_friction: bool) {
+    let chunk_size = cmp::max((batch.len() + num_threads - 1) / num_threads, 1);
+    let num_jobs   = (batch.len() + chunk_size - 1) / chunk_size;
+    let (tx, rx)   = channel();
+
+    for j in range(0, num_jobs) {
+        let tx      = tx.clone();
+        let batch   = batch.clone();
+        let buffers = buffers.clone();
+
+        pool.execute(proc() {
+            let begin = j * chunk_size;
+            let end   = cmp::min(begin + chunk_size, batch.len());
+
+            unsafe {
+                solve_batch_chunk(batch.slice(begin, end), &buffers, is_friction);
+            }
+
+            tx.send(());
+        });
+    }
+
+    // A job that panics drops its sender without sending anything. Wait until every sender is
+    // dropped, so that no job still accesses the buffers when returning.
+    drop(tx);
 
-            if impulse > na::zero() {
-                let bound = c.friction_coeff * impulse;
-                c.lobound = -bound;
-                c.hibound = bound;
+    let mut num_completed = 0u;
 
-                solve_velocity_constraint(c, result);
+    while rx.recv_opt().is_ok() {
+        num_completed = num_completed + 1;
+    }
+
+    if num_completed != num_jobs {
+        panic!("A job of the parallel constraint solver failed.")
+    }
+}
+
+unsafe fn solve_batch_chunk(ids: &[uint], buffers: &SharedBuffers, is_friction: bool) {
+    let restitution = buffers.restitution as *mut VelocityConstraint;
+    let friction    = buffers.friction as *mut VelocityConstraint;
+    let mj_lambda   = buffers.mj_lambda as *mut Velocities;
+
+    for i in ids.iter() {
+        if is_friction {
+            assert!(*i < buffers.num_friction);
+
+            let c = &mut *friction.offset(*i as int);
+
+            assert!(c.friction_limit_id < buffers.num_restitution);
+
+            let impulse = (*restitution.offset(c.friction_limit_id as int)).impulse.clone();
+
+            if set_friction_bounds(c, impulse) {
+                solve_velocity_constraint_at(c, mj_lambda, buffers.num_bodies);
             }
         }
+        else {
+            assert!(*i < buffers.num_restitution);
+
+            let c = &mut *restitution.offset(*i as int);
+
+            solve_velocity_constraint_at(c, mj_lambda, buffers.num_bodies);
+        }
+    }
+}
+
+// Solves one constraint using a local copy of the velocities of its bodies, so that no other
+// velocity is accessed.
+unsafe fn solve_velocity_constraint_at(c:          &mut VelocityConstraint,
+                                       mj_lambda:  *mut Velocities,
+                                       num_bodies: uint) {
+    let id1       = c.id1;
+    let id2       = c.id2;
+    let mut local = [ Velocities::new(), Velocities::new() ];
+
+    if id1 >= 0 {
+        assert!((id1 as uint) < num_bodies);
+        local[0] = (*mj_lambda.offset(id1 as int)).clone();
+        c.id1    = 0;
+    }
+
+    if id2 >= 0 {
+        assert!((id2 as uint) < num_bodies);
+        local[1] = (*mj_lambda.offset(id2 as int)).clone();
+        c.id2    = 1;
+    }
+
+    solve_velocity_constraint(c, local.as_mut_slice(), na::one());
+
+    c.id1 = id1;
+    c.id2 = id2;
+
+    if id1 >= 0 {
+        *mj_lambda.offset(id1 as int) = local[0].clone();
+    }
+
+    if id2 >= 0 {
+        *mj_lambda.offset(id2 as int) = local[1].clone();
+    }
+}
+
+/// Sets the bounds of a friction constraint from the impulse of its restitution constraint.
+///
+/// Returns `false` if the restitution impulse is zero, in which case the friction constraint
+/// should not be solved.
+#[inline(always)]
+pub fn update_friction_bounds(c: &mut VelocityConstraint, restitution: &[VelocityConstraint]) -> bool {
+    let impulse = restitution[c.friction_limit_id].impulse.clone();
+
+    set_friction_bounds(c, impulse)
+}
+
+#[inline(always)]
+fn set_friction_bounds(c: &mut VelocityConstraint, impulse: Scalar) -> bool {
+    if impulse > na::zero() {
+        let bound = c.friction_coeff * impulse;
+        c.lobound = -bound;
+        c.hibound = bound;
+
+        true
+    }
+    else {
+        false
     }
 }
 
 #[inline(always)]
 fn setup_warmstart_for_constraint(c: &VelocityConstraint, mj_lambda: &mut [Velocities]) {
+    apply_impulse(c, c.impulse.clone(), mj_lambda)
+}
+
+/// Applies the variation of impulse `d_lambda` of a constraint to the velocities of its bodies.
+#[inline(always)]
+pub fn apply_impulse(c: &VelocityConstraint, d_lambda: Scalar, mj_lambda: &mut [Velocities]) {
     let id1 = c.id1;
     let id2 = c.id2;
 
     if id1 >= 0 {
-        mj_lambda[id1 as uint].lv = mj_lambda[id1 as uint].lv - c.weighted_normal1 * c.impulse;
-        mj_lambda[id1 as uint].av = mj_lambda[id1 as uint].av + c.weighted_rot_axis1 * c.impulse;
+        mj_lambda[id1 as uint].lv = mj_lambda[id1 as uint].lv - c.weighted_normal1 * d_lambda;
+        mj_lambda[id1 as uint].av = mj_lambda[id1 as uint].av + c.weighted_rot_axis1 * d_lambda;
     }
 
     if id2 >= 0 {
-        mj_lambda[id2 as uint].lv = mj_lambda[id2 as uint].lv + c.weighted_normal2 * c.impulse;
-        mj_lambda[id2 as uint].av = mj_lambda[id2 as uint].av + c.weighted_rot_axis2 * c.impulse;
+        mj_lambda[id2 as uint].lv = mj_lambda[id2 as uint].lv + c.weighted_normal2 * d_lambda;
+        mj_lambda[id2 as uint].av = mj_lambda[id2 as uint].av + c.weighted_rot_axis2 * d_lambda;
     }
 }
 
+/// Solves one constraint and applies the resulting impulse to the velocities of its bodies.
 #[inline(always)]
-fn solve_velocity_constraint(c: &mut VelocityConstraint, mj_lambda: &mut [Velocities]) {
+pub fn solve_velocity_constraint(c: &mut VelocityConstraint, mj_lambda: &mut [Velocities], relaxation: Scalar) {
+    let d_lambda = update_impulse(c, mj_lambda, relaxation);
+
+    apply_impulse(c, d_lambda, mj_lambda)
+}
+
+/// Updates the impulse of one constraint from the current velocities, without applying it.
+///
+/// Returns the variation of the constraint impulse.
+#[inline(always)]
+pub fn update_impulse(c: &mut VelocityConstraint, mj_lambda: &[Velocities], relaxation: Scalar) -> Scalar {
     let id1 = c.id1;
     let id2 = c.id2;
 
@@ -119,24 +401,13 @@ fn solve_velocity_constraint(c: &mut VelocityConstraint, mj_lambda: &mut [Veloci
                                 - na::dot(&c.rot_axis2, &mj_lambda[id2 as uint].av);
     }
 
-    d_lambda_i = d_lambda_i * c.inv_projected_mass;
+    d_lambda_i = (d_lambda_i - c.cfm * c.impulse) * c.inv_projected_mass;
 
     // clamp the value such that: lambda- <= lambda <= lambda+
     // (this is the ``projected'' flavour of Gauss-Seidel
     let lambda_i_0 = c.impulse.clone();
 
-    c.impulse = na::clamp(lambda_i_0 + d_lambda_i, c.lobound, c.hibound);
-
-    d_lambda_i = c.impulse - lambda_i_0;
+    c.impulse = na::clamp(lambda_i_0 + d_lambda_i * relaxation, c.lobound, c.hibound);
 
-
-    if id1 >= 0 {
-        mj_lambda[id1 as uint].lv = mj_lambda[id1 as uint].lv - c.weighted_normal1 * d_lambda_i;
-        mj_lambda[id1 as uint].av = mj_lambda[id1 as uint].av + c.weighted_rot_axis1 * d_lambda_i;
-    }
-
-    if id2 >= 0 {
-        mj_lambda[id2 as uint].lv = mj_lambda[id2 as uint].lv + c.weighted_normal2 * d_lambda_i;
-        mj_lambda[id2 as uint].av = mj_lambda[id2 as uint].av + c.weighted_rot_axis2 * d_lambda_i;
-    }
+    c.impulse - lambda_i_0
 }
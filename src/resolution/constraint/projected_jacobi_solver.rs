@@ -0,0 +1,57 @@
+use math::Scalar;
+use resolution::constraint::velocity_constraint::VelocityConstraint;
+use resolution::constraint::projected_gauss_seidel_solver::Velocities;
+use resolution::constraint::projected_gauss_seidel_solver as pgs;
+
+/// Solve a set of velocity constraints using the projected jacobi solver.
+///
+/// Unlike with the projected gauss seidel solver, every constraint is solved using the velocities
+/// obtained at the end of the previous iteration. The result does not depend on the order of the
+/// constraints but the convergence is slower. Because the impulses of constraints sharing a body
+/// are applied simultaneously, they are multiplied by a `relaxation` factor which should usually
+/// be smaller than one to avoid overshooting.
+///
+/// The other arguments are the same as for `projected_gauss_seidel_solve`.
+pub fn projected_jacobi_solve(restitution:    &mut [VelocityConstraint],
+                              friction:       &mut [VelocityConstraint],
+                              result:         &mut [Velocities],
+                              num_bodies:     uint,
+                              num_iterations: uint,
+                              is_lambda_zero: bool,
+                              relaxation:     Scalar) {
+    assert!(result.len() == num_bodies);
+
+    pgs::init_velocities(restitution, friction, result, is_lambda_zero);
+
+    let mut delta = Vec::from_elem(num_bodies, Velocities::new());
+
+    /*
+     * solve the system
+     */
+    for _ in range(0, num_iterations) {
+        for c in restitution.iter_mut() {
+            let d_lambda = pgs::update_impulse(c, result, relaxation.clone());
+            pgs::apply_impulse(c, d_lambda, delta.as_mut_slice());
+        }
+
+        apply_delta(result, delta.as_mut_slice());
+
+        // The friction bounds depend on the restitution impulses of this iteration.
+        for c in friction.iter_mut() {
+            if pgs::update_friction_bounds(c, restitution) {
+                let d_lambda = pgs::update_impulse(c, result, relaxation.clone());
+                pgs::apply_impulse(c, d_lambda, delta.as_mut_slice());
+            }
+        }
+
+        apply_delta(result, delta.as_mut_slice());
+    }
+}
+
+fn apply_delta(mj_lambda: &mut [Velocities], delta: &mut [Velocities]) {
+    for (v, d) in mj_lambda.iter_mut().zip(delta.iter_mut()) {
+        v.lv = v.lv + d.lv;
+        v.av = v.av + d.av;
+        d.reset();
+    }
+}
@@ -0,0 +1,180 @@
+use std::sync::TaskPool;
+use na;
+use math::Scalar;
+use resolution::constraint::velocity_constraint::VelocityConstraint;
+use resolution::constraint::projected_gauss_seidel_solver::Velocities;
+use resolution::constraint::projected_gauss_seidel_solver as pgs;
+use resolution::constraint::projected_jacobi_solver as jacobi;
+use resolution::constraint::nonsmooth_nonlinear_conjugate_gradient_solver as nncg;
+
+/// Trait implemented by the algorithms solving a set of velocity constraints.
+///
+/// The `AccumulatedImpulseSolver` uses one algorithm for its velocity pass, and one for its
+/// position correction pass.
+pub trait ResolutionAlgorithm {
+    /// Solves a set of velocity constraints.
+    ///
+    /// The arguments are the same as for `projected_gauss_seidel_solve`.
+    fn solve(&self,
+             restitution:    &mut [VelocityConstraint],
+             friction:       &mut [VelocityConstraint],
+             result:         &mut [Velocities],
+             num_bodies:     uint,
+             num_iterations: uint,
+             is_lambda_zero: bool);
+
+    /// Solves a set of velocity constraints using `num_threads` jobs executed on `pool`.
+    ///
+    /// The default implementation solves everything on the calling thread.
+    fn solve_parallel(&self,
+                      restitution:    &mut [VelocityConstraint],
+                      friction:       &mut [VelocityConstraint],
+                      result:         &mut [Velocities],
+                      num_bodies:     uint,
+                      num_iterations: uint,
+                      is_lambda_zero: bool,
+                      _:              &TaskPool,
+                      _:              uint) {
+        self.solve(restitution, friction, result, num_bodies, num_iterations, is_lambda_zero)
+    }
+}
+
+/// The projected gauss seidel algorithm.
+///
+/// When solved in parallel, the constraints are split using graph coloring.
+#[deriving(Clone, Show)]
+pub struct ProjectedGaussSeidel;
+
+impl ProjectedGaussSeidel {
+    /// Creates a new `ProjectedGaussSeidel` algorithm.
+    pub fn new() -> ProjectedGaussSeidel {
+        ProjectedGaussSeidel
+    }
+}
+
+impl ResolutionAlgorithm for ProjectedGaussSeidel {
+    fn solve(&self,
+             restitution:    &mut [VelocityConstraint],
+             friction:       &mut [VelocityConstraint],
+             result:         &mut [Velocities],
+             num_bodies:     uint,
+             num_iterations: uint,
+             is_lambda_zero: bool) {
+        pgs::projected_gauss_seidel_solve(restitution, friction, result, num_bodies, num_iterations,
+                                          is_lambda_zero)
+    }
+
+    fn solve_parallel(&self,
+                      restitution:    &mut [VelocityConstraint],
+                      friction:       &mut [VelocityConstraint],
+                      result:         &mut [Velocities],
+                      num_bodies:     uint,
+                      num_iterations: uint,
+                      is_lambda_zero: bool,
+                      pool:           &TaskPool,
+                      num_threads:    uint) {
+        pgs::colored_projected_gauss_seidel_solve(restitution, friction, result, num_bodies,
+                                                  num_iterations, is_lambda_zero, pool, num_threads)
+    }
+}
+
+/// The successive over-relaxation algorithm.
+#[deriving(Clone, Show)]
+pub struct SuccessiveOverRelaxation {
+    relaxation: Scalar
+}
+
+impl SuccessiveOverRelaxation {
+    /// Creates a new `SuccessiveOverRelaxation` algorithm.
+    ///
+    /// The relaxation factor must be in `]0, 2[`.
+    pub fn new(relaxation: Scalar) -> SuccessiveOverRelaxation {
+        assert!(relaxation > na::zero() && relaxation < na::cast(2.0f64),
+                "The relaxation factor must be in ]0, 2[.");
+
+        SuccessiveOverRelaxation {
+            relaxation: relaxation
+        }
+    }
+
+    /// The relaxation factor of this algorithm.
+    #[inline]
+    pub fn relaxation(&self) -> Scalar {
+        self.relaxation.clone()
+    }
+}
+
+impl ResolutionAlgorithm for SuccessiveOverRelaxation {
+    fn solve(&self,
+             restitution:    &mut [VelocityConstraint],
+             friction:       &mut [VelocityConstraint],
+             result:         &mut [Velocities],
+             num_bodies:     uint,
+             num_iterations: uint,
+             is_lambda_zero: bool) {
+        pgs::successive_over_relaxation_solve(restitution, friction, result, num_bodies, num_iterations,
+                                              is_lambda_zero, self.relaxation.clone())
+    }
+}
+
+/// The projected jacobi algorithm.
+#[deriving(Clone, Show)]
+pub struct ProjectedJacobi {
+    relaxation: Scalar
+}
+
+impl ProjectedJacobi {
+    /// Creates a new `ProjectedJacobi` algorithm.
+    ///
+    /// The relaxation factor must be strictly positive. It should usually be smaller than one.
+    pub fn new(relaxation: Scalar) -> ProjectedJacobi {
+        assert!(relaxation > na::zero(), "The relaxation factor must be strictly positive.");
+
+        ProjectedJacobi {
+            relaxation: relaxation
+        }
+    }
+
+    /// The relaxation factor of this algorithm.
+    #[inline]
+    pub fn relaxation(&self) -> Scalar {
+        self.relaxation.clone()
+    }
+}
+
+impl ResolutionAlgorithm for ProjectedJacobi {
+    fn solve(&self,
+             restitution:    &mut [VelocityConstraint],
+             friction:       &mut [VelocityConstraint],
+             result:         &mut [Velocities],
+             num_bodies:     uint,
+             num_iterations: uint,
+             is_lambda_zero: bool) {
+        jacobi::projected_jacobi_solve(restitution, friction, result, num_bodies, num_iterations,
+                                       is_lambda_zero, self.relaxation.clone())
+    }
+}
+
+/// The nonsmooth nonlinear conjugate gradient algorithm.
+#[deriving(Clone, Show)]
+pub struct NonsmoothNonlinearConjugateGradient;
+
+impl NonsmoothNonlinearConjugateGradient {
+    /// Creates a new `NonsmoothNonlinearConjugateGradient` algorithm.
+    pub fn new() -> NonsmoothNonlinearConjugateGradient {
+        NonsmoothNonlinearConjugateGradient
+    }
+}
+
+impl ResolutionAlgorithm for NonsmoothNonlinearConjugateGradient {
+    fn solve(&self,
+             restitution:    &mut [VelocityConstraint],
+             friction:       &mut [VelocityConstraint],
+             result:         &mut [Velocities],
+             num_bodies:     uint,
+             num_iterations: uint,
+             is_lambda_zero: bool) {
+        nncg::nonsmooth_nonlinear_conjugate_gradient_solve(restitution, friction, result, num_bodies,
+                                                           num_iterations, is_lambda_zero)
+    }
+}
@@ -0,0 +1,116 @@
+use na::{Translate, Rotation};
+use na;
+use detection::joint::{Revolute, Joint};
+use resolution::constraint::ball_in_socket_equation;
+use resolution::constraint::joint_equation_helper;
+use resolution::constraint::velocity_constraint::VelocityConstraint;
+use resolution::constraint::contact_equation::CorrectionParameters;
+use math::{Scalar, Vect, Orientation};
+
+/// The number of velocity constraints generated by a revolute joint.
+pub fn num_equations(joint: &Revolute) -> uint {
+    let mut res = na::dim::<Vect>() + na::dim::<Orientation>() - 1;
+
+    if joint.min_angle().is_some() {
+        res = res + 1;
+    }
+
+    if joint.max_angle().is_some() {
+        res = res + 1;
+    }
+
+    if joint.motor().is_some() {
+        res = res + 1;
+    }
+
+    res
+}
+
+pub fn fill_second_order_equation(dt:          Scalar,
+                                  joint:       &Revolute,
+                                  constraints: &mut [VelocityConstraint],
+                                  correction:  &CorrectionParameters) {
+    let ref1 = joint.anchor1_pos();
+    let ref2 = joint.anchor2_pos();
+
+    ball_in_socket_equation::cancel_relative_linear_motion(
+        dt.clone(),
+        &ref1.translate(&na::orig()),
+        &ref2.translate(&na::orig()),
+        joint.anchor1(),
+        joint.anchor2(),
+        constraints,
+        correction);
+
+    let axis      = na::normalize(&joint_equation_helper::rotate_orientation(&ref1, &joint.axis()));
+    let delta     = ref2 * na::inv(&ref1).expect("ref1 must be inversible.");
+    let delta_rot = delta.rotation();
+
+    // Cancel the relative rotations along every axis orthogonal to the joint axis. In 2D, there is
+    // no such axis.
+    let mut i = na::dim::<Vect>();
+    na::orthonormal_subspace_basis(&axis, |rot_axis: Orientation| {
+        let constraint = &mut constraints[i];
+
+        let dvel  = joint_equation_helper::fill_angular_row(
+            dt.clone(),
+            &rot_axis,
+            joint.anchor1(),
+            joint.anchor2(),
+            constraint);
+        let error = na::dot(&delta_rot, &rot_axis) * correction.joint_corr;
+
+        joint_equation_helper::set_bilateral_objective(dt.clone(), dvel, error, constraint);
+
+        i = i + 1;
+
+        true
+    });
+
+    /*
+     * Angular limits.
+     */
+    let angle = na::dot(&delta_rot, &axis);
+
+    match joint.min_angle() {
+        Some(min_angle) => {
+            let constraint = &mut constraints[i];
+            let dvel = joint_equation_helper::fill_angular_row(
+                dt.clone(), &axis, joint.anchor1(), joint.anchor2(), constraint);
+
+            joint_equation_helper::set_unilateral_objective(
+                dt.clone(), dvel, angle - min_angle, constraint, correction);
+
+            i = i + 1;
+        },
+        None => { }
+    }
+
+    match joint.max_angle() {
+        Some(max_angle) => {
+            let constraint = &mut constraints[i];
+            let dvel = joint_equation_helper::fill_angular_row(
+                dt.clone(), &-axis, joint.anchor1(), joint.anchor2(), constraint);
+
+            joint_equation_helper::set_unilateral_objective(
+                dt.clone(), dvel, max_angle - angle, constraint, correction);
+
+            i = i + 1;
+        },
+        None => { }
+    }
+
+    /*
+     * Motor.
+     */
+    match joint.motor() {
+        Some(motor) => {
+            let constraint = &mut constraints[i];
+            let dvel = joint_equation_helper::fill_angular_row(
+                dt.clone(), &axis, joint.anchor1(), joint.anchor2(), constraint);
+
+            joint_equation_helper::set_motor_objective(dt, dvel, motor, constraint);
+        },
+        None => { }
+    }
+}
@@ -0,0 +1,38 @@
+use std::num::Float;
+use na;
+use detection::joint::{Rope, Joint};
+use resolution::constraint::joint_equation_helper;
+use resolution::constraint::velocity_constraint::VelocityConstraint;
+use resolution::constraint::contact_equation::CorrectionParameters;
+use math::Scalar;
+
+/// The number of velocity constraints generated by a rope.
+pub fn num_equations(_: &Rope) -> uint {
+    1
+}
+
+pub fn fill_second_order_equation(dt:          Scalar,
+                                  joint:       &Rope,
+                                  constraints: &mut [VelocityConstraint],
+                                  correction:  &CorrectionParameters) {
+    let constraint = &mut constraints[0];
+
+    let global1 = joint.anchor1_pos();
+    let global2 = joint.anchor2_pos();
+    let delta   = global2 - global1;
+    let length  = na::norm(&delta);
+
+    if length <= Float::epsilon() {
+        // The rope direction is not well defined, but then it cannot be stretched either.
+        joint_equation_helper::set_null_row(constraint);
+    }
+    else {
+        // A positive impulse along `-axis` pulls the attach points toward each other.
+        let axis = -delta / length;
+        let dvel = joint_equation_helper::fill_linear_row(
+            dt.clone(), &axis, &global1, &global2, joint.anchor1(), joint.anchor2(), constraint);
+
+        joint_equation_helper::set_unilateral_objective(
+            dt, dvel, joint.max_length() - length, constraint, correction);
+    }
+}
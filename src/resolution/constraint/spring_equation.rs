@@ -0,0 +1,35 @@
+use std::num::Float;
+use na;
+use detection::joint::{Spring, Joint};
+use resolution::constraint::joint_equation_helper;
+use resolution::constraint::velocity_constraint::VelocityConstraint;
+use math::Scalar;
+
+/// The number of velocity constraints generated by a spring.
+pub fn num_equations(_: &Spring) -> uint {
+    1
+}
+
+pub fn fill_second_order_equation(dt:          Scalar,
+                                  joint:       &Spring,
+                                  constraints: &mut [VelocityConstraint]) {
+    let constraint = &mut constraints[0];
+
+    let global1 = joint.anchor1_pos();
+    let global2 = joint.anchor2_pos();
+    let delta   = global2 - global1;
+    let length  = na::norm(&delta);
+
+    if length <= Float::epsilon() {
+        // The spring direction is not well defined.
+        joint_equation_helper::set_null_row(constraint);
+    }
+    else {
+        let axis = delta / length;
+        let dvel = joint_equation_helper::fill_linear_row(
+            dt.clone(), &axis, &global1, &global2, joint.anchor1(), joint.anchor2(), constraint);
+
+        joint_equation_helper::set_soft_objective(
+            dt, dvel, length - joint.rest_length(), joint.stiffness(), joint.damping(), constraint);
+    }
+}
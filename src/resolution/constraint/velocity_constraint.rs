@@ -22,8 +22,10 @@ pub struct VelocityConstraint {
     /// The second body rotation axis multiplied by its inverse inertia.
     pub weighted_rot_axis2: Orientation,
 
-    /// The inverse of the sum of linear and angular inertia of both bodies.
+    /// The inverse of the sum of linear and angular inertia of both bodies, and of `cfm`.
     pub inv_projected_mass: Scalar,
+    /// The constraint force mixing term that makes this constraint soft.
+    pub cfm:                Scalar,
 
     /// The total impulse applied.
     pub impulse:            Scalar,
@@ -59,6 +61,7 @@ impl VelocityConstraint {
             weighted_rot_axis2: na::zero(),
 
             inv_projected_mass: na::zero(),
+            cfm:                na::zero(),
 
             impulse:            na::zero(),
             hibound:            na::zero(),
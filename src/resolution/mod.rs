@@ -1,9 +1,17 @@
 //! Constraint resolution.
 
-pub use resolution::solver::Solver;
+pub use resolution::solver::{Solver, ContactImpulse, JointImpulse, SolverState};
 pub use resolution::constraint::accumulated_impulse_solver::AccumulatedImpulseSolver;
-pub use resolution::constraint::contact_equation::CorrectionMode;
-pub use resolution::constraint::projected_gauss_seidel_solver::{Velocities, projected_gauss_seidel_solve};
+pub use resolution::constraint::contact_equation::{CorrectionMode, CorrectionParameters};
+pub use resolution::constraint::joint_equation_helper;
+pub use resolution::constraint::projected_gauss_seidel_solver::{Velocities, projected_gauss_seidel_solve,
+                                                                colored_projected_gauss_seidel_solve,
+                                                                successive_over_relaxation_solve};
+pub use resolution::constraint::projected_jacobi_solver::projected_jacobi_solve;
+pub use resolution::constraint::nonsmooth_nonlinear_conjugate_gradient_solver::nonsmooth_nonlinear_conjugate_gradient_solve;
+pub use resolution::constraint::resolution_algorithm::{ResolutionAlgorithm, ProjectedGaussSeidel,
+                                                       SuccessiveOverRelaxation, ProjectedJacobi,
+                                                       NonsmoothNonlinearConjugateGradient};
 pub use resolution::constraint::impulse_cache::{ImpulseCache, ContactIdentifier};
 pub use resolution::constraint::velocity_constraint::VelocityConstraint;
 
@@ -14,10 +22,22 @@ pub mod solver;
 
 mod constraint {
     pub mod impulse_cache;
+    pub mod joint_impulse_cache;
     pub mod accumulated_impulse_solver;
     pub mod projected_gauss_seidel_solver;
+    pub mod projected_jacobi_solver;
+    pub mod nonsmooth_nonlinear_conjugate_gradient_solver;
+    pub mod resolution_algorithm;
     pub mod velocity_constraint;
     pub mod contact_equation;
     pub mod ball_in_socket_equation;
     pub mod fixed_equation;
+    pub mod revolute_equation;
+    pub mod prismatic_equation;
+    pub mod spring_equation;
+    pub mod rope_equation;
+    pub mod joint_equation_helper;
 }
+
+#[cfg(test)]
+mod tests;
@@ -1,7 +1,96 @@
-use math::Scalar;
+use math::{Scalar, Vect};
+
+/// The impulses applied by a constraint solver at a contact point.
+#[deriving(PartialEq, Show, Clone)]
+pub struct ContactImpulse {
+    /// The index of the contact on the set of constraints given to the solver.
+    pub constraint: uint,
+    /// The impulse applied along the contact normal.
+    pub normal:     Scalar,
+    /// The impulse applied by friction.
+    pub friction:   Vect
+}
+
+/// The impulses applied by a constraint solver to a joint.
+#[deriving(PartialEq, Show, Clone)]
+pub struct JointImpulse {
+    /// The index of the joint on the set of constraints given to the solver.
+    pub constraint: uint,
+    /// The impulse applied by each velocity constraint of the joint.
+    pub impulses:   Vec<Scalar>
+}
+
+/// The data used by a constraint solver for warm-starting.
+///
+/// Its content is only meaningful to the solver that created it.
+#[deriving(PartialEq, Show, Clone, Encodable, Decodable)]
+pub struct SolverState {
+    integers: Vec<uint>,
+    reals:    Vec<Scalar>
+}
+
+impl SolverState {
+    /// Creates a new solver state from integer and real data.
+    pub fn new(integers: Vec<uint>, reals: Vec<Scalar>) -> SolverState {
+        SolverState {
+            integers: integers,
+            reals:    reals
+        }
+    }
+
+    /// The integer data of this state.
+    #[inline]
+    pub fn integers(&self) -> &[uint] {
+        self.integers.as_slice()
+    }
+
+    /// The real data of this state.
+    #[inline]
+    pub fn reals(&self) -> &[Scalar] {
+        self.reals.as_slice()
+    }
+}
 
 /// Trait implemented by constraint solvers.
 pub trait Solver<I> {
     /// Solve the set of constraints of type `I`.
     fn solve(&mut self, Scalar, &[I]);
+
+    /// Solve the set of constraints of type `I`, the joints among them being identified by
+    /// `joint_ids`.
+    ///
+    /// `joint_ids` contains one id per joint, in the order the joints appear in the constraints.
+    /// Those ids remain the same from one call to the next, so solvers can use them to match the
+    /// joints of consecutive steps. Calls `solve` by default.
+    fn solve_with_joint_ids(&mut self, dt: Scalar, constraints: &[I], _: &[uint]) {
+        self.solve(dt, constraints)
+    }
+
+    /// The impulses applied at each contact during the last call to `solve`.
+    ///
+    /// Solvers that do not keep track of them return an empty slice.
+    fn contact_impulses(&self) -> &[ContactImpulse] {
+        &[]
+    }
+
+    /// The impulses applied to each joint during the last call to `solve`.
+    ///
+    /// They are used to break the joints that have a break threshold. Solvers that do not keep
+    /// track of them return an empty slice, and the joints they solve never break.
+    fn joint_impulses(&self) -> &[JointImpulse] {
+        &[]
+    }
+
+    /// A copy of the data used to warm-start the next call to `solve`, if any.
+    ///
+    /// Returns `None` by default.
+    fn warm_start_cache(&self) -> Option<SolverState> {
+        None
+    }
+
+    /// Restores the data used for warm-starting, as returned by `warm_start_cache`.
+    ///
+    /// Does nothing by default.
+    fn set_warm_start_cache(&mut self, _: &SolverState) {
+    }
 }
@@ -0,0 +1,42 @@
+use std::num::Float;
+use na;
+use math::{Scalar, Vect};
+use resolution::{VelocityConstraint, Velocities, nonsmooth_nonlinear_conjugate_gradient_solve};
+
+fn axis(i: uint) -> Vect {
+    na::canonical_basis_element::<Vect>(i).unwrap()
+}
+
+// A contact between the ground and a unit mass falling at unit speed along `-axis(1)`, which stops
+// the mass along `normal`.
+fn resting_contact(normal: Vect) -> VelocityConstraint {
+    let mut c = VelocityConstraint::new();
+
+    c.normal             = normal.clone();
+    c.weighted_normal2   = normal.clone();
+    c.inv_projected_mass = na::one::<Scalar>() / na::sqnorm(&normal);
+    c.objective          = na::dot(&normal, &axis(1));
+    c.hibound            = Float::infinity();
+    c.id2                = 0;
+
+    c
+}
+
+#[test]
+fn nonsmooth_nonlinear_conjugate_gradient_keeps_contact_impulses_within_bounds() {
+    // The mass rests on the floor and touches a 60° slope. The floor alone stops it, and the
+    // unprojected conjugate step makes the slope pull the mass after a few iterations.
+    let sin: Scalar = na::cast(3.0f64.sqrt() / 2.0);
+    let cos: Scalar = na::cast(0.5f64);
+
+    let mut restitution = vec!(resting_contact(axis(0) * sin + axis(1) * cos), resting_contact(axis(1)));
+    let mut friction    = Vec::new();
+    let mut result      = vec!(Velocities::new());
+
+    nonsmooth_nonlinear_conjugate_gradient_solve(restitution.as_mut_slice(), friction.as_mut_slice(),
+                                                 result.as_mut_slice(), 1, 4, true);
+
+    assert!(restitution[0].impulse >= na::zero());
+    assert!(restitution[0].impulse < na::cast(1.0e-6f64));
+    assert!((restitution[1].impulse - na::one()).abs() < na::cast(0.01f64));
+}
@@ -0,0 +1,50 @@
+use ncollide::geometry::Contact;
+use object::RigidBodyHandle;
+use math::{Scalar, Point, Vect};
+
+/// An event generated by the contacts between two rigid bodies during a time step.
+pub enum ContactEvent {
+	/// The two rigid bodies started touching.
+	Started(RigidBodyHandle, RigidBodyHandle),
+	/// The two rigid bodies stopped touching.
+	Stopped(RigidBodyHandle, RigidBodyHandle),
+	/// The contacts between two rigid bodies, resolved by the constraint solver.
+	///
+	/// There is one such event per pair of rigid bodies and per time step. The contact is the one
+	/// that received the largest normal impulse. The last two fields are the total normal and
+	/// friction impulses applied to the second body, summed over every contact point and substep.
+	/// The first body receives the opposite impulses.
+	Contact(RigidBodyHandle, RigidBodyHandle, Contact<Scalar, Point, Vect>, Scalar, Vect)
+}
+
+/// An event generated by a sensor.
+///
+/// The first rigid body is the sensor. Like contact events, this is based on the contacts found by
+/// the narrow phase: a rigid body enters the sensor when the first contact between them appears,
+/// and leaves it when the last contact disappears.
+pub enum SensorEvent {
+	/// A rigid body entered the sensor.
+	Entered(RigidBodyHandle, RigidBodyHandle),
+	/// A rigid body left the sensor.
+	Exited(RigidBodyHandle, RigidBodyHandle)
+}
+
+impl Clone for SensorEvent {
+	fn clone(&self) -> SensorEvent {
+		match *self {
+			SensorEvent::Entered(ref s, ref b) => SensorEvent::Entered(s.clone(), b.clone()),
+			SensorEvent::Exited(ref s, ref b)  => SensorEvent::Exited(s.clone(), b.clone())
+		}
+	}
+}
+
+impl Clone for ContactEvent {
+	fn clone(&self) -> ContactEvent {
+		match *self {
+			ContactEvent::Started(ref b1, ref b2) => ContactEvent::Started(b1.clone(), b2.clone()),
+			ContactEvent::Stopped(ref b1, ref b2) => ContactEvent::Stopped(b1.clone(), b2.clone()),
+			ContactEvent::Contact(ref b1, ref b2, ref c, ref n, ref f) =>
+				ContactEvent::Contact(b1.clone(), b2.clone(), c.clone(), n.clone(), f.clone())
+		}
+	}
+}
@@ -1,5 +1,10 @@
 //! The physics world.
 
-pub use world::world::{World, WorldBroadPhase, RigidBodyCollisionWorld/*, RigidBodies*/};
+pub use world::world::{World, WorldSnapshot, WorldBroadPhase, RigidBodyCollisionWorld/*, RigidBodies*/};
+pub use world::events::{ContactEvent, SensorEvent};
 
 mod world;
+mod events;
+
+#[cfg(test)]
+mod tests;
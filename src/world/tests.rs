@@ -0,0 +1,428 @@
+use std::num::Float;
+use std::sync::{Arc, RWLock};
+use na;
+use ncollide::shape::{Ball, Plane};
+use math::{Scalar, Point, Vect, Matrix};
+use object::{RigidBody, RigidBodyHandle, ActivationState};
+use detection::joint::{Anchor, Fixed, BallInSocket};
+use world::{World, ContactEvent, SensorEvent};
+use integration::{ParallelIntegrator, BodyExpMapIntegrator, BodyDamping};
+#[cfg(feature = "3d")]
+use ncollide::shape::Cuboid;
+#[cfg(feature = "3d")]
+use integration::BodySymplecticIntegrator;
+
+fn axis(i: uint) -> Vect {
+	na::canonical_basis_element::<Vect>(i).unwrap()
+}
+
+fn gravity() -> Vect {
+	axis(1) * na::cast::<f64, Scalar>(-9.81)
+}
+
+fn ball(radius: f64) -> RigidBody {
+	RigidBody::new_dynamic(Ball::new(na::cast(radius)), na::cast(1.0f64), na::cast(0.3f64), na::cast(0.5f64))
+}
+
+// A horizontal chain of balls attached to the world origin, swinging down onto a static ball.
+fn swinging_chain(world: &mut World) -> Vec<RigidBodyHandle> {
+	let half_link: Vect = axis(0) * na::cast::<f64, Scalar>(0.5);
+	let mut bodies      = Vec::new();
+	let mut prev        = Anchor::new(None, na::orig::<Point>() + half_link);
+
+	world.set_gravity(gravity());
+	world.set_deterministic(true);
+
+	let mut ground = RigidBody::new_static(Ball::new(na::cast(2.0f64)), na::cast(0.3f64), na::cast(0.5f64));
+	ground.append_translation(&(axis(1) * na::cast::<f64, Scalar>(-5.0)));
+	let _ = world.add_body(ground);
+
+	for i in range(0u, 5) {
+		let mut rb = ball(0.4);
+		rb.append_translation(&(axis(0) * na::cast::<f64, Scalar>((i + 1) as f64)));
+
+		let body   = world.add_body(rb);
+		let anchor = Anchor::new(Some(body.clone()), na::orig::<Point>() - half_link);
+
+		let _ = world.add_ball_in_socket(BallInSocket::new(prev, anchor));
+
+		prev = Anchor::new(Some(body.clone()), na::orig::<Point>() + half_link);
+		bodies.push(body);
+	}
+
+	bodies
+}
+
+// A ball resting on a static plane, which never falls asleep.
+fn resting_ball(world: &mut World) -> RigidBodyHandle {
+	let ground = RigidBody::new_static(Plane::new(axis(1)), na::cast(0.3f64), na::cast(0.0f64));
+	let _      = world.add_body(ground);
+
+	let mut rb = ball(0.5);
+	rb.append_translation(&(axis(1) * na::cast::<f64, Scalar>(0.5)));
+	rb.set_deactivation_threshold(None);
+
+	world.add_body(rb)
+}
+
+// A ball hanging from the world origin by a fixed joint.
+fn hanging_ball(world: &mut World) -> Arc<RWLock<Fixed>> {
+	let body    = world.add_body(ball(0.5));
+	let anchor1 = Anchor::new(None, na::one::<Matrix>());
+	let anchor2 = Anchor::new(Some(body), na::one::<Matrix>());
+
+	world.add_fixed(Fixed::new(anchor1, anchor2))
+}
+
+#[test]
+fn joint_broken_during_substeps_is_reported_once() {
+	let mut world = World::new();
+	world.set_gravity(gravity());
+	world.set_substeps(4);
+
+	let joint = hanging_ball(&mut world);
+	world.joint_manager().set_break_threshold(&joint, Some(na::cast(0.001f64)));
+
+	world.step(na::cast(1.0f64 / 60.0));
+
+	assert_eq!(world.broken_joints().len(), 1);
+	assert!(world.joint_manager().joints().elements().is_empty());
+
+	world.step(na::cast(1.0f64 / 60.0));
+
+	assert_eq!(world.broken_joints().len(), 0);
+}
+
+#[test]
+fn contact_events_are_reported_once_per_step_with_substeps() {
+	let mut world = World::new();
+	world.set_gravity(gravity());
+	world.set_substeps(4);
+
+	let body = resting_ball(&mut world);
+	let dt: Scalar = na::cast(1.0f64 / 60.0);
+
+	for _ in range(0u, 60) {
+		world.step(dt.clone());
+	}
+
+	let weight = na::norm(&gravity()) / body.read().inv_mass();
+	let mut num_contacts = 0u;
+
+	for event in world.contact_events().iter() {
+		match *event {
+			ContactEvent::Contact(_, _, _, normal, _) => {
+				// The impulses of the four substeps are summed.
+				assert!((normal - weight * dt).abs() < weight * dt * na::cast(0.1f64));
+				num_contacts = num_contacts + 1;
+			},
+			_ => panic!("The ball should keep touching the ground.")
+		}
+	}
+
+	assert_eq!(num_contacts, 1);
+}
+
+#[test]
+fn contact_events_start_carry_impulses_and_stop() {
+	let mut world = World::new();
+	world.set_gravity(gravity());
+
+	let body = resting_ball(&mut world);
+	let dt: Scalar = na::cast(1.0f64 / 60.0);
+
+	let mut started = false;
+	let mut pushed  = false;
+
+	for _ in range(0u, 60) {
+		world.step(dt.clone());
+
+		for event in world.contact_events().iter() {
+			match *event {
+				ContactEvent::Started(..) => {
+					assert!(!started);
+					started = true;
+				},
+				ContactEvent::Contact(_, _, _, normal, _) => {
+					assert!(started);
+					pushed = pushed || normal > na::zero();
+				},
+				ContactEvent::Stopped(..) => panic!("The ball should keep touching the ground.")
+			}
+		}
+	}
+
+	assert!(started && pushed);
+
+	// Throw the ball away from the ground.
+	body.write().set_lin_vel(axis(1) * na::cast::<f64, Scalar>(10.0));
+
+	let mut stopped = false;
+
+	for _ in range(0u, 10) {
+		world.step(dt.clone());
+
+		for event in world.contact_events().iter() {
+			match *event {
+				ContactEvent::Stopped(..) => stopped = true,
+				_                         => { }
+			}
+		}
+	}
+
+	assert!(stopped);
+}
+
+#[test]
+fn deterministic_jointed_scene_is_reproducible() {
+	let mut world1 = World::new();
+	let mut world2 = World::new();
+	let bodies1    = swinging_chain(&mut world1);
+	let bodies2    = swinging_chain(&mut world2);
+
+	for _ in range(0u, 120) {
+		world1.step(na::cast(1.0f64 / 60.0));
+		world2.step(na::cast(1.0f64 / 60.0));
+	}
+
+	for (b1, b2) in bodies1.iter().zip(bodies2.iter()) {
+		assert!(*b1.read().position() == *b2.read().position());
+	}
+}
+
+#[test]
+fn restored_snapshot_replays_the_same_steps() {
+	let mut world1 = World::new();
+	let mut world2 = World::new();
+	let bodies1    = swinging_chain(&mut world1);
+	let bodies2    = swinging_chain(&mut world2);
+
+	for _ in range(0u, 30) {
+		world1.step(na::cast(1.0f64 / 60.0));
+	}
+
+	let snapshot = world1.snapshot();
+
+	for _ in range(0u, 60) {
+		world1.step(na::cast(1.0f64 / 60.0));
+	}
+
+	let expected: Vec<Matrix> = bodies1.iter().map(|b| b.read().position().clone()).collect();
+
+	// The snapshot does not depend on the body addresses, so it can be restored on another world
+	// with the same bodies.
+	world1.restore(&snapshot);
+	world2.restore(&snapshot);
+
+	for _ in range(0u, 60) {
+		world1.step(na::cast(1.0f64 / 60.0));
+		world2.step(na::cast(1.0f64 / 60.0));
+	}
+
+	for ((b1, b2), pos) in bodies1.iter().zip(bodies2.iter()).zip(expected.iter()) {
+		assert!(*b1.read().position() == *pos);
+		assert!(*b2.read().position() == *pos);
+	}
+}
+
+#[test]
+fn restore_removes_the_bodies_added_after_the_snapshot() {
+	let mut world = World::new();
+	let bodies    = swinging_chain(&mut world);
+
+	for _ in range(0u, 30) {
+		world.step(na::cast(1.0f64 / 60.0));
+	}
+
+	let snapshot = world.snapshot();
+
+	for _ in range(0u, 60) {
+		world.step(na::cast(1.0f64 / 60.0));
+	}
+
+	let expected: Vec<Matrix> = bodies.iter().map(|b| b.read().position().clone()).collect();
+
+	world.restore(&snapshot);
+
+	// A ball jointed to the end of the chain, which disturbs it until the next restore.
+	let mut rb = ball(0.4);
+	rb.append_translation(&(axis(0) * na::cast::<f64, Scalar>(6.0)));
+
+	let extra  = world.add_body(rb);
+	let anchor = Anchor::new(Some(extra.clone()), na::orig::<Point>());
+	let last   = Anchor::new(Some(bodies[bodies.len() - 1].clone()), na::orig::<Point>() + axis(0));
+	let _      = world.add_ball_in_socket(BallInSocket::new(last, anchor));
+
+	for _ in range(0u, 20) {
+		world.step(na::cast(1.0f64 / 60.0));
+	}
+
+	world.restore(&snapshot);
+
+	assert!(*extra.read().activation_state() == ActivationState::Deleted);
+	assert_eq!(world.bodies().count(), bodies.len() + 1);
+	assert!(world.joint_manager().joints_with_body(&extra).is_none());
+
+	for _ in range(0u, 60) {
+		world.step(na::cast(1.0f64 / 60.0));
+	}
+
+	for (b, pos) in bodies.iter().zip(expected.iter()) {
+		assert!(*b.read().position() == *pos);
+	}
+}
+
+// A ball crossing a static sensor ball at the origin in two seconds, along a straight line
+// passing by `closest`.
+fn sensor_crossing(world: &mut World, closest: Vect) -> (RigidBodyHandle, RigidBodyHandle) {
+	let mut sensor = RigidBody::new_static(Ball::new(na::cast(0.5f64)), na::cast(0.3f64), na::cast(0.5f64));
+	sensor.set_sensor(true);
+
+	let dir: Vect = na::normalize(&(axis(0) + axis(1)));
+	let mut rb    = ball(0.5);
+	rb.append_translation(&(closest - dir * na::cast::<f64, Scalar>(3.0)));
+	rb.set_lin_vel(dir * na::cast::<f64, Scalar>(3.0));
+	rb.set_deactivation_threshold(None);
+
+	(world.add_body(sensor), world.add_body(rb))
+}
+
+#[test]
+fn sensor_events_follow_the_narrow_phase_contacts() {
+	let mut world    = World::new();
+	let (sensor, rb) = sensor_crossing(&mut world, na::zero());
+	let lin_vel      = rb.read().lin_vel();
+	let mut entered  = None;
+	let mut exited   = None;
+
+	for i in range(0u, 120) {
+		world.step(na::cast(1.0f64 / 60.0));
+
+		for event in world.sensor_events().iter() {
+			match *event {
+				SensorEvent::Entered(ref s, ref b) => {
+					assert!(entered.is_none());
+					assert_eq!(s.read().stable_id(), sensor.read().stable_id());
+					assert_eq!(b.read().stable_id(), rb.read().stable_id());
+					entered = Some(i);
+				},
+				SensorEvent::Exited(_, _) => {
+					assert!(exited.is_none());
+					exited = Some(i);
+				}
+			}
+		}
+
+		assert!(world.contact_events().is_empty());
+	}
+
+	assert!(entered.is_some() && exited.is_some() && entered < exited);
+	// The sensor does not push the ball.
+	assert!(rb.read().lin_vel() == lin_vel);
+}
+
+#[test]
+fn sensor_events_ignore_intersecting_bounding_volumes() {
+	let mut world = World::new();
+
+	// The ball passes at a distance of 1.4 from the sensor, while their AABBs intersect.
+	let closest = (axis(1) - axis(0)) * na::cast::<f64, Scalar>(0.99);
+	let _       = sensor_crossing(&mut world, closest);
+
+	for _ in range(0u, 120) {
+		world.step(na::cast(1.0f64 / 60.0));
+
+		assert!(world.sensor_events().is_empty());
+	}
+}
+
+#[test]
+fn global_damping_is_combined_with_the_body_damping() {
+	let mut world = World::new();
+	world.set_damping(BodyDamping::new(na::cast(0.5f64), na::one()));
+
+	let mut rb = ball(0.5);
+	rb.set_lin_vel(axis(0));
+	rb.set_lin_damping(na::cast(2.0f64));
+
+	let body = world.add_body(rb);
+	let dt: Scalar = na::cast(1.0f64 / 60.0);
+
+	world.step(dt);
+
+	let expected = na::cast::<f64, Scalar>(0.5) * (-na::cast::<f64, Scalar>(2.0) * dt).exp();
+
+	assert!((na::dot(&body.read().lin_vel(), &axis(0)) - expected).abs() < na::cast(1.0e-5f64));
+}
+
+#[test]
+fn exp_map_integrator_is_exact_for_a_constant_acceleration() {
+	let mut world = World::new();
+	world.set_gravity(gravity());
+	world.set_integrator(box BodyExpMapIntegrator::new() as Box<ParallelIntegrator<RigidBody> + Send + Sync>);
+
+	let body = world.add_body(ball(0.5));
+
+	for _ in range(0u, 60) {
+		world.step(na::cast(1.0f64 / 60.0));
+	}
+
+	// After one second, the ball fell by g / 2.
+	let expected = gravity() * na::cast::<f64, Scalar>(0.5);
+	let actual   = na::translation(body.read().position());
+
+	assert!(na::norm(&(actual - expected)) < na::cast(1.0e-3f64));
+}
+
+#[cfg(feature = "3d")]
+fn rotational_energy(rb: &RigidBody) -> Scalar {
+	let inertia = na::inv(rb.inv_inertia()).unwrap();
+	let av      = rb.ang_vel();
+
+	na::dot(&av, &(inertia * av)) * na::cast::<f64, Scalar>(0.5)
+}
+
+#[cfg(feature = "3d")]
+#[test]
+fn symplectic_integrator_keeps_the_energy_of_a_spinning_box_bounded() {
+	let mut world = World::new();
+	world.set_integrator(box BodySymplecticIntegrator::new() as Box<ParallelIntegrator<RigidBody> + Send + Sync>);
+
+	let half_extents = Vect::new(na::cast(0.5f64), na::cast(1.0f64), na::cast(2.0f64));
+	let mut rb       = RigidBody::new_dynamic(Cuboid::new(half_extents), na::cast(1.0f64), na::cast(0.3f64),
+											  na::cast(0.5f64));
+
+	// Fast spin around the intermediate axis of inertia, which is unstable.
+	rb.set_ang_vel(Vect::new(na::cast(0.1f64), na::cast(20.0f64), na::cast(0.1f64)));
+	rb.set_deactivation_threshold(None);
+
+	let body    = world.add_body(rb);
+	let initial = rotational_energy(&*body.read());
+
+	for _ in range(0u, 600) {
+		world.step(na::cast(1.0f64 / 60.0));
+
+		assert!(rotational_energy(&*body.read()) <= initial * na::cast(1.01f64));
+	}
+}
+
+#[test]
+fn update_keeps_the_fraction_of_a_step_left_when_behind() {
+	let mut world = World::new();
+	world.set_timestep(na::cast(0.1f64));
+	world.set_max_steps(2);
+
+	let eps: Scalar = na::cast(1.0e-4f64);
+
+	// 1.5 steps: one step is performed, and half a step is left.
+	assert_eq!(world.update(na::cast(0.15f64)), 1);
+	assert!((world.interpolation_factor() - na::cast(0.5f64)).abs() < eps);
+
+	// 5.5 steps accumulated: only `max_steps` are performed, and half a step is still left.
+	assert_eq!(world.update(na::cast(0.5f64)), 2);
+	assert!((world.interpolation_factor() - na::cast(0.5f64)).abs() < eps);
+
+	// The half step left is used by the next update.
+	assert_eq!(world.update(na::cast(0.08f64)), 1);
+	assert!((world.interpolation_factor() - na::cast(0.3f64)).abs() < eps);
+}
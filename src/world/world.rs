@@ -1,9 +1,15 @@
 use std::sync::Arc;
 use std::sync::RWLock;
+use std::sync::TaskPool;
+use std::comm::channel;
+use std::mem;
+use std::cmp;
+use std::collections::HashMap as StdHashMap;
 use std::iter::Map;
 use std::slice::Iter;
 use na;
 use ncollide::bounding_volume::AABB;
+use ncollide::geometry::Contact;
 use ncollide::ray::{Ray, RayIntersection};
 use ncollide::narrow_phase::ShapeShapeCollisionDetector;
 use ncollide::utils::data::hash_map::{HashMap, Entry};
@@ -11,14 +17,15 @@ use ncollide::utils::data::hash::UintTWHash;
 use ncollide::broad_phase::{BroadPhase, DBVTBroadPhase, ProximitySignalHandler};
 use ncollide::narrow_phase::ContactSignalHandler;
 use ncollide::world::{CollisionWorld, CollisionObject};
-use integration::{Integrator, BodySmpEulerIntegrator, BodyForceGenerator,
-				  TranslationalCCDMotionClamping};
+use integration::{Integrator, ParallelIntegrator, BodySmpEulerIntegrator, BodyForceGenerator,
+				  BodyDamping, TranslationalCCDMotionClamping};
 use detection::ActivationManager;
 use detection::Detector;
-use detection::constraint::Constraint;
-use detection::joint::{JointManager, BallInSocket, Fixed};
-use resolution::{Solver, AccumulatedImpulseSolver, CorrectionMode};
-use object::{RigidBody, RigidBodyHandle};
+use detection::constraint::{Constraint, ContactModifier};
+use detection::joint::{JointManager, JointManagerSnapshot, BallInSocket, Fixed, Revolute, Prismatic, Spring, Rope, CustomJoint, CustomJointHandle};
+use resolution::{Solver, AccumulatedImpulseSolver, SolverState, CorrectionMode};
+use object::{RigidBody, RigidBodyHandle, RigidBodySnapshot, MaterialTable};
+use world::events::{ContactEvent, SensorEvent};
 use math::{Scalar, Point, Vect, Orientation, Matrix};
 
 /// The default broad phase.
@@ -29,6 +36,9 @@ pub type RigidBodiesFn<'a> = |&'a Entry<uint, Arc<RWLock<RigidBody>>>|:'a -> &'a
 /// collision world
 pub type RigidBodyCollisionWorld = CollisionWorld<Scalar, Point, Vect, Matrix, Arc<RWLock<RigidBody>>>;
 
+// Pairs of rigid bodies, indexed by `pair_key`.
+type BodyPairs = StdHashMap<(uint, uint), (RigidBodyHandle, RigidBodyHandle)>;
+
 /// The physics world.
 ///
 /// This is the main structure of the physics engine.
@@ -36,13 +46,34 @@ pub struct World {
 	cworld:      RigidBodyCollisionWorld,
 	bodies:      HashMap<uint, RigidBodyHandle, UintTWHash>,
 	forces:      BodyForceGenerator,
-	integrator:  BodySmpEulerIntegrator,
+	damping:     BodyDamping,
+	integrator:  Box<ParallelIntegrator<RigidBody> + Send + Sync>,
 	sleep:       ActivationManager,
 	ccd:         TranslationalCCDMotionClamping,
 	joints:      JointManager,
 	solver:      AccumulatedImpulseSolver,
+	custom:      Option<Box<Solver<Constraint> + 'static>>,
+	touching:    BodyPairs,
+	sensing:     BodyPairs,
+	impulses:    Vec<PairImpulses>,
+	impulse_ids: StdHashMap<(uint, uint), uint>,
+	events:      Vec<ContactEvent>,
+	modifier:    Option<Box<ContactModifier + 'static>>,
+	materials:   MaterialTable,
+	sensors:     Vec<SensorEvent>,
+	next_id:     uint,
+	determinism: bool,
+	timestep:    Scalar,
+	max_steps:   uint,
+	accumulator: Scalar,
+	substeps:    uint,
+	num_threads: uint,
+	pool:        Option<TaskPool>
 }
 
+// Minimum number of rigid bodies for them to be integrated in parallel.
+static PARALLEL_INTEGRATION_THRESHOLD: uint = 256;
+
 impl World {
 	/// Creates a new physics world.
 	pub fn new() -> World {
@@ -52,7 +83,8 @@ impl World {
 
 		// For the intergration
 		let forces     = BodyForceGenerator::new(na::zero(), na::zero());
-		let integrator = BodySmpEulerIntegrator::new();
+		let damping    = BodyDamping::new(na::one(), na::one());
+		let integrator = box BodySmpEulerIntegrator::new() as Box<ParallelIntegrator<RigidBody> + Send + Sync>;
 
 		/*
 		 * For the collision detection
@@ -81,92 +113,558 @@ impl World {
 			10);
 
 		World {
-			cworld:     cworld,
-			bodies:     HashMap::new(UintTWHash::new()),
-			forces:     forces,
-			integrator: integrator,
-			sleep:      sleep,
-			ccd:        ccd,
-			joints:     joints,
-			solver:     solver,
+			cworld:      cworld,
+			bodies:      HashMap::new(UintTWHash::new()),
+			forces:      forces,
+			damping:     damping,
+			integrator:  integrator,
+			sleep:       sleep,
+			ccd:         ccd,
+			joints:      joints,
+			solver:      solver,
+			custom:      None,
+			touching:    StdHashMap::new(),
+			sensing:     StdHashMap::new(),
+			impulses:    Vec::new(),
+			impulse_ids: StdHashMap::new(),
+			events:      Vec::new(),
+			modifier:    None,
+			materials:   MaterialTable::new(),
+			sensors:     Vec::new(),
+			next_id:     0,
+			determinism: false,
+			timestep:    na::cast(1.0f64 / 60.0),
+			max_steps:   5,
+			accumulator: na::zero(),
+			substeps:    1,
+			num_threads: 1,
+			pool:        None
 		}
 	}
 
 	/// Updates the physics world.
 	pub fn step(&mut self, dt: Scalar) {
-		for e in self.bodies.elements_mut().iter_mut() {
-			let mut rb = e.value.write();
+		self.events.clear();
+		self.sensors.clear();
+		self.joints.clear_broken();
+
+		self.do_step(dt);
+	}
+
+	/// Advances the physics world by `elapsed` seconds of real time, using fixed time steps.
+	///
+	/// The time that does not fill a whole time step is accumulated until the next call. At most
+	/// `max_steps` steps are performed, and only the fraction of a step left is kept to avoid
+	/// falling further and further behind. The contact and sensor events of every step performed are
+	/// kept, as well as the joints broken during every step. Returns the number of steps performed.
+	pub fn update(&mut self, elapsed: Scalar) -> uint {
+		self.events.clear();
+		self.sensors.clear();
+		self.joints.clear_broken();
+
+		self.accumulator = self.accumulator + elapsed;
+
+		let mut nsteps = 0;
+
+		while self.accumulator >= self.timestep && nsteps < self.max_steps {
+			let dt = self.timestep.clone();
+
+			self.do_step(dt.clone());
+			self.accumulator = self.accumulator - dt;
+			nsteps = nsteps + 1;
+		}
+
+		if self.accumulator >= self.timestep {
+			self.accumulator = self.accumulator % self.timestep;
+		}
+
+		nsteps
+	}
+
+	/// The fixed time step used by `update`.
+	pub fn timestep(&self) -> Scalar {
+		self.timestep.clone()
+	}
+
+	/// Sets the fixed time step used by `update`.
+	pub fn set_timestep(&mut self, timestep: Scalar) {
+		assert!(timestep > na::zero(), "The time step must be strictly positive.");
+		self.timestep = timestep
+	}
+
+	/// The maximum number of steps performed by one call to `update`.
+	pub fn max_steps(&self) -> uint {
+		self.max_steps
+	}
+
+	/// Sets the maximum number of steps performed by one call to `update`.
+	pub fn set_max_steps(&mut self, max_steps: uint) {
+		self.max_steps = max_steps
+	}
+
+	/// The number of substeps performed by each time step.
+	pub fn substeps(&self) -> uint {
+		self.substeps
+	}
+
+	/// Sets the number of substeps performed by each time step.
+	///
+	/// The integration, narrow phase and constraint resolution are run `substeps` times with a
+	/// time step divided by `substeps`, while the broad phase and the sleeping management are
+	/// run once. This improves the stability of long joint chains and large mass ratios.
+	///
+	/// The impulses applied to a joint are summed over the substeps before being compared to its
+	/// break threshold, so the threshold does not depend on the number of substeps. Likewise, the
+	/// contact events are generated once per step, with the impulses of every substep.
+	pub fn set_substeps(&mut self, substeps: uint) {
+		assert!(substeps > 0, "The number of substeps must be at least 1.");
+		self.substeps = substeps
+	}
+
+	/// The number of threads used to integrate the rigid bodies and to solve the constraints.
+	pub fn num_threads(&self) -> uint {
+		self.num_threads
+	}
+
+	/// Sets the number of threads used to integrate the rigid bodies and to solve the constraints.
+	///
+	/// With more than one thread, the bodies are integrated concurrently when there are enough of
+	/// them, and the default constraint solver solves independent islands concurrently (see
+	/// `AccumulatedImpulseSolver::set_num_threads`). This does not change the results. The collision
+	/// detection is always performed on the calling thread. Defaults to 1.
+	pub fn set_num_threads(&mut self, num_threads: uint) {
+		assert!(num_threads > 0, "The number of threads must be at least 1.");
+
+		if num_threads != self.num_threads {
+			self.num_threads = num_threads;
+			self.pool        = if num_threads > 1 { Some(TaskPool::new(num_threads)) } else { None };
+		}
+
+		self.solver.set_num_threads(num_threads)
+	}
+
+	/// The fraction of a time step accumulated by `update` but not simulated yet.
+	///
+	/// This is the factor to give to `RigidBody::interpolated_position` for rendering.
+	pub fn interpolation_factor(&self) -> Scalar {
+		self.accumulator / self.timestep
+	}
+
+	/// The position of a rigid body, interpolated between its last two states.
+	pub fn interpolated_position(&self, b: &RigidBodyHandle) -> Matrix {
+		b.read().interpolated_position(self.interpolation_factor())
+	}
 
-			if rb.is_active() {
-				self.forces.update(dt.clone(), rb.deref_mut());
-				self.integrator.update(dt.clone(), rb.deref_mut());
-				self.cworld.set_next_position(&e.value, rb.position().clone());
+	fn do_step(&mut self, dt: Scalar) {
+		let subdt       = dt / na::cast(self.substeps as f64);
+		let first_event  = self.events.len();
+		let first_sensor = self.sensors.len();
+
+		self.joints.clear_impulses();
+
+		for i in range(0u, self.substeps) {
+			let first = i == 0;
+			let last  = i == self.substeps - 1;
+
+			match self.pool {
+				Some(ref pool) if self.bodies.len() >= PARALLEL_INTEGRATION_THRESHOLD => {
+					integrate_parallel(pool, self.num_threads, self.bodies.elements().as_slice(), dt.clone(),
+									   subdt.clone(), first, last, &self.forces, &self.damping, &*self.integrator)
+				},
+				_ => {
+					for e in self.bodies.elements().iter() {
+						integrate(e.value.write().deref_mut(), dt.clone(), subdt.clone(), first, last,
+								  &mut self.forces, &mut self.damping, &mut *self.integrator)
+					}
+				}
+			}
+
+			for e in self.bodies.elements().iter() {
+				let rb = e.value.read();
+
+				if rb.is_active() {
+					self.cworld.set_next_position(&e.value, rb.position().clone());
+				}
 			}
+
+			self.cworld.perform_position_update();
+
+			// The broad phase margins are large enough to find the pairs of every substep.
+			if first {
+				self.cworld.perform_broad_phase();
+			}
+
+			self.ccd.update(&mut self.cworld);
+			self.cworld.perform_narrow_phase();
+
+			if first {
+				self.joints.update(&mut self.sleep);
+				self.sleep.update(&mut self.cworld, &self.joints, &self.bodies);
+			}
+
+			self.solve_constraints(subdt.clone(), last);
 		}
 
-		self.cworld.perform_position_update();
-		self.cworld.perform_broad_phase();
-		self.ccd.update(&mut self.cworld);
-		self.cworld.perform_narrow_phase();
+		// One contact event per pair of bodies, with the impulses of every substep.
+		for pair in mem::replace(&mut self.impulses, Vec::new()).into_iter() {
+			self.events.push(ContactEvent::Contact(pair.b1, pair.b2, pair.contact, pair.normal,
+												   pair.friction));
+		}
 
-		self.joints.update(&mut self.sleep);
-		self.sleep.update(&mut self.cworld, &self.joints, &self.bodies);
+		self.impulse_ids.clear();
 
+		if self.determinism {
+			self.events.slice_from_mut(first_event).sort_by(|e1, e2| event_order(e1).cmp(&event_order(e2)));
+			self.sensors.slice_from_mut(first_sensor).sort_by(|e1, e2| {
+				sensor_event_order(e1).cmp(&sensor_event_order(e2))
+			});
+		}
+	}
+
+	// Contacts starting or stopping, and bodies entering or leaving sensors, are detected at the
+	// `last` substep only.
+	fn solve_constraints(&mut self, dt: Scalar, last: bool) {
 		// XXX: use `self.collector` instead to avoid allocation.
 		let mut collector = Vec::new();
+		let mut touching  = StdHashMap::new();
+		let mut sensing   = StdHashMap::new();
+		let materials     = &self.materials;
 
 		self.cworld.contacts(|b1, b2, c| {
-			if b1.read().is_active() || b2.read().is_active() {
-				let m1 = b1.read().margin();
-				let m2 = b2.read().margin();
+			let rb1 = b1.read();
+			let rb2 = b2.read();
+
+			// Sensors only generate sensor events.
+			if rb1.is_sensor() || rb2.is_sensor() {
+				let key = pair_key(b1, b2);
 
-				let mut c = c.clone();
-				c.depth = c.depth + m1 + m2;
+				if !sensing.contains_key(&key) {
+					// The sensor comes first.
+					let pair = if rb1.is_sensor() { (b1.clone(), b2.clone()) } else { (b2.clone(), b1.clone()) };
+					let _    = sensing.insert(key, pair);
+				}
 
-				collector.push(Constraint::RBRB(b1.clone(), b2.clone(), c));
+				return;
+			}
+
+			let key = pair_key(b1, b2);
+
+			if !touching.contains_key(&key) {
+				let _ = touching.insert(key, (b1.clone(), b2.clone()));
+			}
+
+			match contact_constraint(b1, &*rb1, b2, &*rb2, c, materials) {
+				Some(constraint) => collector.push(constraint),
+				None             => { }
 			}
 		});
 
+		if self.determinism {
+			// The collision world reports contacts in an order that depends on the bodies addresses.
+			for c in collector.iter_mut() {
+				orient_contact(c);
+			}
+
+			collector.sort_by(|c1, c2| constraint_order(c1).cmp(&constraint_order(c2)));
+		}
+
+		match self.modifier {
+			Some(ref mut modifier) => {
+				let contacts = mem::replace(&mut collector, Vec::new());
+
+				for contact in contacts.into_iter() {
+					match contact {
+						Constraint::RBRB(b1, b2, mut c, mut props) => {
+							if modifier.modify(&b1, &b2, &mut c, &mut props) {
+								collector.push(Constraint::RBRB(b1, b2, c, props));
+							}
+						},
+						other => collector.push(other)
+					}
+				}
+			},
+			None => { }
+		}
+
+		if last {
+			for (b1, b2) in new_pairs(&touching, &self.touching).into_iter() {
+				self.events.push(ContactEvent::Started(b1, b2));
+			}
+
+			for (b1, b2) in new_pairs(&self.touching, &touching).into_iter() {
+				self.events.push(ContactEvent::Stopped(b1, b2));
+			}
+
+			for (sensor, b) in new_pairs(&sensing, &self.sensing).into_iter() {
+				self.sensors.push(SensorEvent::Entered(sensor, b));
+			}
+
+			for (sensor, b) in new_pairs(&self.sensing, &sensing).into_iter() {
+				self.sensors.push(SensorEvent::Exited(sensor, b));
+			}
+
+			self.touching = touching;
+			self.sensing  = sensing;
+		}
+
+		let first_joint = collector.len();
+
 		self.joints.interferences(&mut collector);
 
-		self.solver.solve(dt, collector.as_slice());
+		if self.determinism {
+			// The joint manager reports joints in an order that depends on their addresses.
+			let joints = &self.joints;
+
+			collector.slice_from_mut(first_joint).sort_by(|j1, j2| {
+				joints.joint_id(j1).cmp(&joints.joint_id(j2))
+			});
+		}
+
+		// The joint ids let the solver match the joints of consecutive steps.
+		let joint_ids: Vec<uint> = {
+			let joints = &self.joints;
+
+			collector.slice_from(first_joint).iter().map(|j| joints.joint_id(j).unwrap()).collect()
+		};
+
+		let solver: &mut Solver<Constraint> = match self.custom {
+			Some(ref mut custom) => &mut **custom,
+			None                 => &mut self.solver as &mut Solver<Constraint>
+		};
+
+		solver.solve_with_joint_ids(dt, collector.as_slice(), joint_ids.as_slice());
+
+		for imp in solver.joint_impulses().iter() {
+			self.joints.add_impulses(&collector[imp.constraint], imp.impulses.as_slice());
+		}
+
+		self.joints.remove_broken(&mut self.sleep);
+
+		for imp in solver.contact_impulses().iter() {
+			match collector[imp.constraint] {
+				Constraint::RBRB(ref b1, ref b2, ref c, _) => {
+					let key = pair_key(b1, b2);
+
+					match self.impulse_ids.get(&key).map(|i| *i) {
+						Some(i) => self.impulses[i].add(b1, c, imp.normal.clone(), imp.friction.clone()),
+						None    => {
+							let _ = self.impulse_ids.insert(key, self.impulses.len());
+							self.impulses.push(PairImpulses::new(b1, b2, c, imp.normal.clone(),
+																 imp.friction.clone()));
+						}
+					}
+				},
+				_ => { }
+			}
+		}
 
 		collector.clear();
 	}
 
 	/// Adds a rigid body to the physics world.
-	pub fn add_body(&mut self, rb: RigidBody) -> RigidBodyHandle {
-		// XXX: dont create the collision object here.
-		let co = CollisionObject::new_shared(
-			rb.position().clone(),
-			rb.shape().clone(),
-			rb.collision_groups().clone());
+	pub fn add_body(&mut self, mut rb: RigidBody) -> RigidBodyHandle {
+		rb.save_position();
+		rb.set_stable_id(self.next_id);
+		self.next_id = self.next_id + 1;
 
 		let handle = Arc::new(RWLock::new(rb));
 
-		self.bodies.insert(handle.deref() as *const RWLock<RigidBody> as uint, handle.clone());
-		self.cworld.add(handle.clone(), co);
+		self.bodies.insert(handle.read().stable_id(), handle.clone());
+		self.add_collision_object(&handle);
 
 		handle
 	}
 
+	fn add_collision_object(&mut self, handle: &RigidBodyHandle) {
+		// XXX: dont create the collision object here.
+		let co = {
+			let rb = handle.read();
+
+			CollisionObject::new_shared(
+				rb.position().clone(),
+				rb.shape().clone(),
+				rb.collision_groups().clone())
+		};
+
+		self.cworld.add(handle.clone(), co);
+	}
+
+	/// Whether this world runs in deterministic mode.
+	pub fn is_deterministic(&self) -> bool {
+		self.determinism
+	}
+
+	/// Enables or disables the deterministic mode.
+	///
+	/// In deterministic mode, the rigid bodies, contacts, joints and contact events are processed in
+	/// an order depending only on the insertion order of the rigid bodies and joints, instead of
+	/// their memory address. Two runs of the same scene on the same build then produce identical
+	/// results. This is disabled by default because it requires contacts and joints to be sorted at
+	/// each time step.
+	pub fn set_deterministic(&mut self, deterministic: bool) {
+		self.determinism = deterministic;
+
+		if deterministic {
+			self.sort_bodies();
+		}
+	}
+
+	/// Captures the simulation state of this world.
+	///
+	/// This includes the state of the rigid bodies and joints, and the data used for warm-starting
+	/// and continuous collision detection. The snapshot can be serialized: the shapes, materials
+	/// and collision groups of the rigid bodies are not part of it.
+	pub fn snapshot(&self) -> WorldSnapshot {
+		let bodies = self.bodies.elements().iter().map(|e| e.value.read().snapshot()).collect();
+		let mut touching: Vec<(uint, uint)> =
+			self.touching.values().map(|&(ref b1, ref b2)| id_pair(b1, b2)).collect();
+		let mut sensing: Vec<(uint, uint)> =
+			self.sensing.values().map(|&(ref s, ref b)| (s.read().stable_id(), b.read().stable_id())).collect();
+
+		touching.sort();
+		sensing.sort();
+
+		WorldSnapshot {
+			bodies:      bodies,
+			joints:      self.joints.snapshot(),
+			touching:    touching,
+			sensing:     sensing,
+			solver:      self.active_solver().warm_start_cache(),
+			ccd:         self.ccd.last_positions(),
+			next_id:     self.next_id,
+			accumulator: self.accumulator.clone()
+		}
+	}
+
+	/// Restores the simulation state captured by `snapshot`.
+	///
+	/// The rigid bodies are matched with the ones of this world by stable id, and their handles
+	/// are updated in-place. Bodies added after the snapshot was taken are removed from the world
+	/// as by `remove_body`. Bodies removed since then cannot be restored because their shape is
+	/// not part of the snapshot. The joints are restored by `JointManager::restore`.
+	pub fn restore(&mut self, snapshot: &WorldSnapshot) {
+		let mut saved = StdHashMap::new();
+
+		for body in snapshot.bodies.iter() {
+			let _ = saved.insert(body.stable_id(), body);
+		}
+
+		let mut removed = Vec::new();
+
+		for e in self.bodies.elements().iter() {
+			match saved.get(&e.key) {
+				Some(body) => {
+					e.value.write().restore(*body);
+
+					let position = e.value.read().position().clone();
+					self.cworld.set_next_position(&e.value, position);
+				},
+				None => removed.push(e.value.clone())
+			}
+		}
+
+		for body in removed.iter() {
+			self.detach_body(body);
+		}
+
+		self.cworld.perform_position_update();
+
+		if self.determinism {
+			self.sort_bodies();
+		}
+
+		self.joints.restore(&snapshot.joints, &self.bodies);
+		self.touching = restored_pairs(&self.bodies, snapshot.touching.as_slice());
+		self.sensing  = restored_pairs(&self.bodies, snapshot.sensing.as_slice());
+
+		match snapshot.solver {
+			Some(ref cache) => self.active_solver_mut().set_warm_start_cache(cache),
+			None            => { }
+		}
+
+		self.ccd.set_last_positions(snapshot.ccd.as_slice());
+		self.next_id = snapshot.next_id;
+		self.accumulator = snapshot.accumulator.clone();
+		self.events.clear();
+		self.sensors.clear();
+	}
+
 	/// Remove a rigid body from the physics world.
 	pub fn remove_body(&mut self, b: &RigidBodyHandle) {
+		self.detach_body(b);
+
+		if self.determinism {
+			self.sort_bodies();
+		}
+	}
+
+	// Removes a rigid body from every structure of the world, and wakes up the bodies it was
+	// touching or attached to.
+	fn detach_body(&mut self, b: &RigidBodyHandle) {
+		let key = b.deref() as *const RWLock<RigidBody>;
+
+		for &(ref b1, ref b2) in self.touching.values() {
+			if b1.deref() as *const RWLock<RigidBody> == key {
+				self.sleep.will_activate(b2);
+			}
+			else if b2.deref() as *const RWLock<RigidBody> == key {
+				self.sleep.will_activate(b1);
+			}
+		}
+
 		self.cworld.remove(b);
 		self.joints.remove(b, &mut self.sleep);
 		self.ccd.remove_ccd_from(b);
-		self.bodies.remove(&(b.deref() as *const RWLock<RigidBody> as uint));
+		self.bodies.remove(&b.read().stable_id());
 		b.write().delete();
 	}
 
+	// Removing a body changes the order of the other bodies.
+	fn sort_bodies(&mut self) {
+		let mut bodies: Vec<(uint, RigidBodyHandle)> =
+			self.bodies.elements().iter().map(|e| (e.key, e.value.clone())).collect();
+
+		bodies.sort_by(|&(id1, _), &(id2, _)| id1.cmp(&id2));
+		self.bodies.clear();
+
+		for (id, body) in bodies.into_iter() {
+			self.bodies.insert(id, body);
+		}
+	}
+
 	/// Gets a mutable reference to the force generator.
 	pub fn forces_generator(&mut self) -> &mut BodyForceGenerator {
 		&mut self.forces
 	}
 
+	/// Gets a mutable reference to the velocity damper.
+	pub fn damping(&mut self) -> &mut BodyDamping {
+		&mut self.damping
+	}
+
+	/// Sets the velocity damper.
+	///
+	/// Its global coefficients are combined with the damping coefficients of each rigid body. The
+	/// default damper only applies the latter.
+	pub fn set_damping(&mut self, damping: BodyDamping) {
+		self.damping = damping
+	}
+
 	/// Gets a mutable reference to the position and orientation integrator.
-	pub fn integrator(&mut self) -> &mut BodySmpEulerIntegrator {
-		&mut self.integrator
+	pub fn integrator(&mut self) -> &mut (ParallelIntegrator<RigidBody> + Send + Sync) {
+		&mut *self.integrator
+	}
+
+	/// Sets the position and orientation integrator.
+	///
+	/// The default integrator is the `BodySmpEulerIntegrator`. Use the `BodyExpEulerIntegrator` for
+	/// explicit Euler, the `BodyExpMapIntegrator` to move the bodies with the exponential map of
+	/// their mean velocities, and the `BodySymplecticIntegrator` for fast-spinning 3D objects.
+	///
+	/// The integrator is copied to integrate the bodies concurrently when several threads are used
+	/// (see `set_num_threads`).
+	pub fn set_integrator(&mut self, integrator: Box<ParallelIntegrator<RigidBody> + Send + Sync>) {
+		self.integrator = integrator
 	}
 
 	/// Gets a mutable reference to the collision detector.
@@ -184,11 +682,81 @@ impl World {
 		&mut self.joints
 	}
 
-	/// Gets a mutable reference to the constraint solver.
+	/// The contact events generated during the last time step.
+	pub fn contact_events(&self) -> &[ContactEvent] {
+		self.events.as_slice()
+	}
+
+	/// Removes and returns the contact events generated during the last time step.
+	pub fn drain_contact_events(&mut self) -> Vec<ContactEvent> {
+		mem::replace(&mut self.events, Vec::new())
+	}
+
+	/// The sensor events generated during the last time step.
+	pub fn sensor_events(&self) -> &[SensorEvent] {
+		self.sensors.as_slice()
+	}
+
+	/// Removes and returns the sensor events generated during the last time step.
+	pub fn drain_sensor_events(&mut self) -> Vec<SensorEvent> {
+		mem::replace(&mut self.sensors, Vec::new())
+	}
+
+	/// Gets a mutable reference to the table overriding the contact properties of specific pairs
+	/// of materials.
+	pub fn material_table(&mut self) -> &mut MaterialTable {
+		&mut self.materials
+	}
+
+	/// Sets the hook run on every contact before it is given to the constraint solver.
+	///
+	/// Set this to `None` to remove the current hook.
+	pub fn set_contact_modifier(&mut self, modifier: Option<Box<ContactModifier + 'static>>) {
+		self.modifier = modifier
+	}
+
+	/// The joints broken during the last call to `step` or `update`.
+	///
+	/// Those joints have already been removed from the world.
+	pub fn broken_joints(&self) -> &[Constraint] {
+		self.joints.broken_joints()
+	}
+
+	/// Gets a mutable reference to the default constraint solver.
+	///
+	/// This solver is used unless another one is set with `set_constraints_solver`.
 	pub fn constraints_solver(&mut self) -> &mut AccumulatedImpulseSolver {
 		&mut self.solver
 	}
 
+	/// Gets a mutable reference to the constraint solver set with `set_constraints_solver`, if
+	/// any.
+	pub fn custom_constraints_solver(&mut self) -> Option<&mut (Solver<Constraint> + 'static)> {
+		self.custom.as_mut().map(|s| &mut **s)
+	}
+
+	/// Sets the constraint solver used instead of the default `AccumulatedImpulseSolver`.
+	///
+	/// Set this to `None` to use the default solver again. The default solver keeps its settings
+	/// and its warm-starting data while it is not used.
+	pub fn set_constraints_solver(&mut self, solver: Option<Box<Solver<Constraint> + 'static>>) {
+		self.custom = solver
+	}
+
+	fn active_solver(&self) -> &Solver<Constraint> {
+		match self.custom {
+			Some(ref custom) => &**custom,
+			None             => &self.solver as &Solver<Constraint>
+		}
+	}
+
+	fn active_solver_mut(&mut self) -> &mut Solver<Constraint> {
+		match self.custom {
+			Some(ref mut custom) => &mut **custom,
+			None                 => &mut self.solver as &mut Solver<Constraint>
+		}
+	}
+
 	/// Sets the linear acceleration afecting every dynamic rigid body.
 	pub fn set_gravity(&mut self, gravity: Vect) {
 		self.forces.set_lin_acc(gravity)
@@ -259,17 +827,96 @@ impl World {
 		self.joints.remove_joint(joint, &mut self.sleep)
 	}
 
+	/// Adds a revolute joint to the world.
+	pub fn add_revolute(&mut self, joint: Revolute) -> Arc<RWLock<Revolute>> {
+		let res = Arc::new(RWLock::new(joint));
+
+		self.joints.add_revolute(res.clone(), &mut self.sleep);
+
+		res
+	}
+
+	/// Removes a revolute joint from the world.
+	pub fn remove_revolute(&mut self, joint: &Arc<RWLock<Revolute>>) {
+		self.joints.remove_joint(joint, &mut self.sleep)
+	}
+
+	/// Adds a prismatic joint to the world.
+	pub fn add_prismatic(&mut self, joint: Prismatic) -> Arc<RWLock<Prismatic>> {
+		let res = Arc::new(RWLock::new(joint));
+
+		self.joints.add_prismatic(res.clone(), &mut self.sleep);
+
+		res
+	}
+
+	/// Removes a prismatic joint from the world.
+	pub fn remove_prismatic(&mut self, joint: &Arc<RWLock<Prismatic>>) {
+		self.joints.remove_joint(joint, &mut self.sleep)
+	}
+
+	/// Adds a spring to the world.
+	pub fn add_spring(&mut self, joint: Spring) -> Arc<RWLock<Spring>> {
+		let res = Arc::new(RWLock::new(joint));
+
+		self.joints.add_spring(res.clone(), &mut self.sleep);
+
+		res
+	}
+
+	/// Removes a spring from the world.
+	pub fn remove_spring(&mut self, joint: &Arc<RWLock<Spring>>) {
+		self.joints.remove_joint(joint, &mut self.sleep)
+	}
+
+	/// Adds a rope to the world.
+	pub fn add_rope(&mut self, joint: Rope) -> Arc<RWLock<Rope>> {
+		let res = Arc::new(RWLock::new(joint));
+
+		self.joints.add_rope(res.clone(), &mut self.sleep);
+
+		res
+	}
+
+	/// Removes a rope from the world.
+	pub fn remove_rope(&mut self, joint: &Arc<RWLock<Rope>>) {
+		self.joints.remove_joint(joint, &mut self.sleep)
+	}
+
+	/// Adds a user-defined joint to the world.
+	pub fn add_custom_joint(&mut self, joint: Box<CustomJoint + Send + Sync>) -> CustomJointHandle {
+		let res = Arc::new(RWLock::new(joint));
+
+		self.joints.add_custom_joint(res.clone(), &mut self.sleep);
+
+		res
+	}
+
+	/// Removes a user-defined joint from the world.
+	pub fn remove_custom_joint(&mut self, joint: &CustomJointHandle) {
+		self.joints.remove_joint(joint, &mut self.sleep)
+	}
+
 	/// Collects every interferences detected since the last update.
+	///
+	/// Those are the constraints given to the solver: the contacts involving a sensor or no active
+	/// dynamic body are left out.
 	pub fn interferences(&mut self, out: &mut Vec<Constraint>) {
 		// FIXME: ugly.
+		let materials = &self.materials;
+
 		self.cworld.contacts(|b1, b2, c| {
-			let m1 = b1.read().margin();
-			let m2 = b2.read().margin();
+			let rb1 = b1.read();
+			let rb2 = b2.read();
 
-			let mut c = c.clone();
-			c.depth = c.depth + m1 + m2;
+			if rb1.is_sensor() || rb2.is_sensor() {
+				return;
+			}
 
-			out.push(Constraint::RBRB(b1.clone(), b2.clone(), c));
+			match contact_constraint(b1, &*rb1, b2, &*rb2, c, materials) {
+				Some(constraint) => out.push(constraint),
+				None             => { }
+			}
 		});
 
 		self.joints.interferences(out);
@@ -302,3 +949,246 @@ impl World {
 		self.cworld.unregister_contact_signal_handler(name)
 	}
 }
+
+/// The simulation state of a `World`, captured by `World::snapshot`.
+///
+/// The rigid bodies are identified by their stable id, and the joints by their identifier in the
+/// joint manager.
+#[deriving(Clone, Encodable, Decodable)]
+pub struct WorldSnapshot {
+	bodies:      Vec<RigidBodySnapshot>,
+	joints:      JointManagerSnapshot,
+	touching:    Vec<(uint, uint)>,
+	// The sensor comes first.
+	sensing:     Vec<(uint, uint)>,
+	solver:      Option<SolverState>,
+	ccd:         Vec<(uint, Vect, bool)>,
+	next_id:     uint,
+	accumulator: Scalar
+}
+
+fn id_pair(b1: &RigidBodyHandle, b2: &RigidBodyHandle) -> (uint, uint) {
+	let id1 = b1.read().stable_id();
+	let id2 = b2.read().stable_id();
+
+	if id1 < id2 { (id1, id2) } else { (id2, id1) }
+}
+
+fn constraint_order(c: &Constraint) -> (uint, uint) {
+	match *c {
+		Constraint::RBRB(ref b1, ref b2, _, _) => id_pair(b1, b2),
+		_ => (0, 0)
+	}
+}
+
+fn event_order(e: &ContactEvent) -> (uint, uint) {
+	match *e {
+		ContactEvent::Started(ref b1, ref b2)          => id_pair(b1, b2),
+		ContactEvent::Stopped(ref b1, ref b2)          => id_pair(b1, b2),
+		ContactEvent::Contact(ref b1, ref b2, _, _, _) => id_pair(b1, b2)
+	}
+}
+
+fn sensor_event_order(e: &SensorEvent) -> (uint, uint) {
+	match *e {
+		SensorEvent::Entered(ref s, ref b) => (s.read().stable_id(), b.read().stable_id()),
+		SensorEvent::Exited(ref s, ref b)  => (s.read().stable_id(), b.read().stable_id())
+	}
+}
+
+// The pairs of `pairs` missing from `old`.
+fn new_pairs(pairs: &BodyPairs, old: &BodyPairs) -> Vec<(RigidBodyHandle, RigidBodyHandle)> {
+	pairs.iter().filter(|&(key, _)| !old.contains_key(key)).map(|(_, pair)| pair.clone()).collect()
+}
+
+// Looks up the pairs of stable ids saved in a snapshot. Pairs with a missing body are dropped.
+fn restored_pairs(bodies: &HashMap<uint, RigidBodyHandle, UintTWHash>, ids: &[(uint, uint)]) -> BodyPairs {
+	let mut pairs = StdHashMap::new();
+
+	for &(id1, id2) in ids.iter() {
+		match (bodies.find(&id1), bodies.find(&id2)) {
+			(Some(b1), Some(b2)) => {
+				let _ = pairs.insert(pair_key(b1, b2), (b1.clone(), b2.clone()));
+			},
+			_ => { }
+		}
+	}
+
+	pairs
+}
+
+// The constraint of a contact between two bodies that are not sensors.
+//
+// Contacts between non-dynamic bodies (static or kinematic), or between sleeping bodies, are not
+// solved.
+fn contact_constraint(b1:        &RigidBodyHandle,
+					  rb1:       &RigidBody,
+					  b2:        &RigidBodyHandle,
+					  rb2:       &RigidBody,
+					  c:         &Contact<Scalar, Point, Vect>,
+					  materials: &MaterialTable)
+					  -> Option<Constraint> {
+	if (rb1.is_active() || rb2.is_active()) && (rb1.can_move() || rb2.can_move()) {
+		let mut c = c.clone();
+		c.depth = c.depth + rb1.margin() + rb2.margin();
+
+		Some(Constraint::RBRB(b1.clone(), b2.clone(), c,
+							  materials.contact_properties(rb1.material(), rb2.material())))
+	}
+	else {
+		None
+	}
+}
+
+// Makes the body with the smallest stable id the first body of the contact.
+fn orient_contact(constraint: &mut Constraint) {
+	match *constraint {
+		Constraint::RBRB(ref mut b1, ref mut b2, ref mut c, _) => {
+			let swap = b1.read().stable_id() > b2.read().stable_id();
+
+			if swap {
+				mem::swap(b1, b2);
+				flip_contact(c);
+			}
+		},
+		_ => { }
+	}
+}
+
+// Exchanges the roles of the two bodies of a contact.
+fn flip_contact(c: &mut Contact<Scalar, Point, Vect>) {
+	mem::swap(&mut c.world1, &mut c.world2);
+	c.normal = -c.normal;
+}
+
+// The impulses applied between two rigid bodies during the substeps of a time step.
+struct PairImpulses {
+	b1:       RigidBodyHandle,
+	b2:       RigidBodyHandle,
+	// The contact that received the largest normal impulse.
+	contact:  Contact<Scalar, Point, Vect>,
+	largest:  Scalar,
+	normal:   Scalar,
+	friction: Vect
+}
+
+impl PairImpulses {
+	fn new(b1:       &RigidBodyHandle,
+		   b2:       &RigidBodyHandle,
+		   contact:  &Contact<Scalar, Point, Vect>,
+		   normal:   Scalar,
+		   friction: Vect)
+		   -> PairImpulses {
+		PairImpulses {
+			b1:       b1.clone(),
+			b2:       b2.clone(),
+			contact:  contact.clone(),
+			largest:  normal.clone(),
+			normal:   normal,
+			friction: friction
+		}
+	}
+
+	fn add(&mut self,
+		   b1:       &RigidBodyHandle,
+		   contact:  &Contact<Scalar, Point, Vect>,
+		   normal:   Scalar,
+		   friction: Vect) {
+		let mut contact  = contact.clone();
+		let mut friction = friction;
+
+		// The collision world may report the bodies of a pair in any order.
+		if b1.deref() as *const RWLock<RigidBody> != self.b1.deref() as *const RWLock<RigidBody> {
+			flip_contact(&mut contact);
+			friction = -friction;
+		}
+
+		if normal > self.largest {
+			self.contact = contact;
+			self.largest = normal.clone();
+		}
+
+		self.normal   = self.normal + normal;
+		self.friction = self.friction + friction;
+	}
+}
+
+// Moves one rigid body forward by `subdt`, `dt` being the length of the whole time step.
+fn integrate(rb:         &mut RigidBody,
+			 dt:         Scalar,
+			 subdt:      Scalar,
+			 first:      bool,
+			 last:       bool,
+			 forces:     &mut BodyForceGenerator,
+			 damping:    &mut BodyDamping,
+			 integrator: &mut (ParallelIntegrator<RigidBody> + Send + Sync)) {
+	if first {
+		rb.save_position();
+	}
+
+	if rb.is_active() {
+		if rb.is_kinematic() {
+			// The target must be reached at the end of the whole step.
+			rb.update_kinematic_velocities(dt);
+		}
+
+		forces.update(subdt.clone(), rb);
+		damping.update(subdt.clone(), rb);
+		integrator.update(subdt, rb);
+	}
+
+	if last {
+		rb.clear_forces();
+	}
+}
+
+// Integrates the rigid bodies on `num_threads` jobs, each using its own copy of the integrators.
+fn integrate_parallel(pool:        &TaskPool,
+					  num_threads: uint,
+					  bodies:      &[Entry<uint, RigidBodyHandle>],
+					  dt:          Scalar,
+					  subdt:       Scalar,
+					  first:       bool,
+					  last:        bool,
+					  forces:      &BodyForceGenerator,
+					  damping:     &BodyDamping,
+					  integrator:  &(ParallelIntegrator<RigidBody> + Send + Sync)) {
+	let chunk_size   = cmp::max((bodies.len() + num_threads - 1) / num_threads, 1);
+	let (tx, rx)     = channel();
+	let mut num_jobs = 0u;
+
+	for entries in bodies.chunks(chunk_size) {
+		let tx             = tx.clone();
+		let chunk          = entries.iter().map(|e| e.value.clone()).collect::<Vec<RigidBodyHandle>>();
+		let mut forces     = forces.clone();
+		let mut damping    = damping.clone();
+		let mut integrator = integrator.clone_integrator();
+		let dt             = dt.clone();
+		let subdt          = subdt.clone();
+
+		pool.execute(proc() {
+			for b in chunk.iter() {
+				integrate(b.write().deref_mut(), dt.clone(), subdt.clone(), first, last,
+						  &mut forces, &mut damping, &mut *integrator)
+			}
+
+			tx.send(());
+		});
+
+		num_jobs = num_jobs + 1;
+	}
+
+	// A job that panics drops its sender without sending anything.
+	drop(tx);
+
+	if rx.iter().count() != num_jobs {
+		panic!("A job of the parallel integration failed.")
+	}
+}
+
+fn pair_key(b1: &RigidBodyHandle, b2: &RigidBodyHandle) -> (uint, uint) {
+	let k1 = b1.deref() as *const RWLock<RigidBody> as uint;
+	let k2 = b2.deref() as *const RWLock<RigidBody> as uint;
+
+	if k1 < k2 { (k1, k2) } else { (k2, k1) }
+}